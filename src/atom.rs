@@ -97,6 +97,9 @@ where T::Value: Clone + Eq + Hash
         }
     }
 
+    /// Every value registered so far, in no particular order
+    pub fn values(&self) -> Vec<T::Value> { self.0.read().fwd.keys().cloned().collect() }
+
     pub fn memoize<Q: ?Sized + Hash + Eq>(&self, val: &Q) -> Option<T>
     where T::Value: std::borrow::Borrow<Q> {
         let inner = self.0.read();
@@ -186,5 +189,23 @@ macro_rules! easy_atom {
 
             fn from_str(s: &str) -> Result<Self, $err_ty> { Self::new(s) }
         }
+
+        impl ::serde::Serialize for $ty {
+            /// Serializes as the atom's value rather than its id, which is
+            /// only meaningful within one run
+            fn serialize<S: ::serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+                crate::atom::Memoized::registry_ref()
+                    .peek_value(*self, |v: &$value| ::serde::Serialize::serialize(v, ser))
+                    .unwrap()
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $ty {
+            fn deserialize<D: ::serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+                let val = <$value as ::serde::Deserialize>::deserialize(de)?;
+
+                Self::new(&val).map_err(::serde::de::Error::custom)
+            }
+        }
     };
 }
@@ -1,9 +1,91 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::{export::Output, plan::Objective};
 
 #[derive(Parser)]
 pub struct Opts {
-    #[clap(short, long, default_value = "config.yml")]
+    #[clap(short, long, global = true, default_value = "config.yml")]
     pub config: PathBuf,
+
+    /// Write every plan calculated in the REPL to this file as a Graphviz graph
+    #[clap(long)]
+    pub dot: Option<PathBuf>,
+
+    /// Print calculated plans and the show command's output as text, json or yaml
+    #[clap(long, global = true, default_value = "text")]
+    pub format: Output,
+
+    /// Run the REPL commands in this file instead of prompting for them.
+    /// Commands are also read from standard input when it isn't a terminal.
+    #[clap(long)]
+    pub script: Option<PathBuf>,
+
+    /// Print each command read from a script before running it
+    #[clap(long)]
+    pub echo: bool,
+
+    /// Keep running a script after a command fails, instead of stopping
+    #[clap(long)]
+    pub keep_going: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Generate a config file from the game's Docs.json
+    Import {
+        /// The path to Docs.json, found in the game's Community Resources folder
+        docs: PathBuf,
+
+        /// The file to write the config to, instead of standard output
+        #[clap(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    #[clap(flatten)]
+    Exec(ExecCommand),
+}
+
+/// The subcommands handled as the equivalent REPL commands would be.  Item and
+/// recipe names are only known once the config is loaded, so they are taken as
+/// strings and parsed afterwards.
+#[derive(Subcommand)]
+pub enum ExecCommand {
+    /// Calculate a production plan and print it
+    Plan {
+        /// An output to produce, with an optional rate first, such as "60 screw"
+        #[clap(short, long, required = true)]
+        want: Vec<String>,
+
+        /// An available input, with an optional rate first, such as "120 coal"
+        #[clap(long)]
+        have: Vec<String>,
+
+        /// A recipe to enable, such as an alternate
+        #[clap(short, long)]
+        enable: Vec<String>,
+
+        /// What to use the least of: raw, power or buildings
+        #[clap(short, long)]
+        minimize: Option<Objective>,
+    },
+
+    /// List the recipes declared in the config
+    Recipes {
+        /// Only list the recipes producing this item
+        item: Option<String>,
+    },
+
+    /// List the items described in the config
+    Items {
+        /// Only describe this item
+        item: Option<String>,
+    },
+
+    /// Check that the config loads, and list the loops among all its recipes
+    Validate,
 }
@@ -1,45 +1,167 @@
-use std::{fs::File, path::Path, sync::Arc};
+use std::{borrow::Cow, collections::BTreeMap, fmt, fs::File, path::Path, str::FromStr, sync::Arc};
+
+use num_traits::{ToPrimitive, Zero};
+use serde::{de, ser, Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
-    items::{Amount, Item, ItemStacks, Machine},
+    items::{recipe_key, Amount, Item, ItemStacks, Machine, Rate, RecipeName, TimeUnit},
     prelude::*,
     HashMap, HashSet,
 };
 
-mod format {
-    use std::fmt;
+pub mod format {
+    use std::{collections::BTreeMap, fmt};
 
     use lazy_static::lazy_static;
     use regex::Regex;
-    use serde::{de, Deserialize};
+    use serde::{de, ser, Deserialize, Serialize};
 
+    use super::Form;
     use crate::{
-        items::{Amount, ItemStack, ItemStacks},
+        items::{Amount, ItemStack, ItemStacks, Rate, TimeUnit},
         prelude::*,
         HashMap,
     };
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Serialize)]
     #[serde(deny_unknown_fields)]
     pub struct Config {
-        pub raw: Vec<String>,
-        pub recipes: HashMap<String, Vec<Recipe>>,
+        pub raw: Vec<Raw>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub items: BTreeMap<String, ItemInfo>,
+        pub machines: BTreeMap<String, Machine>,
+        pub recipes: BTreeMap<String, Vec<Recipe>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub logistics: Option<Logistics>,
+    }
+
+    /// A raw resource, either just its name or its name mapped to the nodes
+    /// and limits it can be extracted from
+    #[derive(Deserialize, Serialize)]
+    #[serde(untagged)]
+    pub enum Raw {
+        Unlimited(String),
+        Limited(BTreeMap<String, Resource>),
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Resource {
+        #[serde(default)]
+        pub nodes: Nodes,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub extractors: BTreeMap<String, Rate>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub limit: Option<Rate>,
+    }
+
+    #[derive(Default, Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Nodes {
+        #[serde(default)]
+        pub impure: u32,
+        #[serde(default)]
+        pub normal: u32,
+        #[serde(default)]
+        pub pure: u32,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct ItemInfo {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub name: Option<String>,
+        #[serde(default)]
+        pub form: Form,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub stack_size: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sink_points: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub category: Option<String>,
+    }
+
+    /// The capacities of each tier of belt and pipe, keyed by tier name
+    #[derive(Default, Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Logistics {
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub belts: BTreeMap<String, Rate>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub pipes: BTreeMap<String, Rate>,
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Machine {
+        pub power: Amount,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub footprint: Option<Footprint>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub cost: Option<Items>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub inputs: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub outputs: Option<u32>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Footprint {
+        pub width: Amount,
+        pub length: Amount,
+    }
+
+    #[derive(Deserialize, Serialize)]
     #[serde(deny_unknown_fields)]
     pub struct Recipe {
         pub make: Items,
         pub from: Items,
-        #[serde(rename = "in")]
-        pub in_sec: Amount,
+        #[serde(rename = "in", skip_serializing_if = "Option::is_none")]
+        pub in_sec: Option<Amount>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub name: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub alternate: bool,
     }
 
     #[derive(Debug)]
     pub struct Items(pub Vec<Item>);
     #[derive(Debug)]
-    pub struct Item(pub Amount, pub String);
+    pub struct Item(pub Rate, pub String);
+
+    impl Items {
+        pub fn has_rates(&self) -> bool { self.0.iter().any(|i| i.0.unit().is_some()) }
+
+        /// Convert into the stacks consumed or produced by one recipe cycle,
+        /// scaling any amounts given as rates by the cycle duration
+        pub fn into_stacks(self, cycle_sec: &Amount) -> Result<ItemStacks> {
+            self.0
+                .into_iter()
+                .map(|Item(rate, name)| {
+                    let amt = if rate.unit().is_some() {
+                        rate.per_min_or(TimeUnit::Minute) * cycle_sec
+                            / Amount::from_integer(60)
+                    } else {
+                        rate.amount().clone()
+                    };
+
+                    Ok((name.try_into()?, amt))
+                })
+                .collect::<Result<HashMap<_, _>>>()
+                .map(ItemStacks::new)
+        }
+    }
+
+    impl Raw {
+        pub fn names(&self) -> Vec<&String> {
+            match self {
+                Self::Unlimited(name) => vec![name],
+                Self::Limited(map) => map.keys().collect(),
+            }
+        }
+    }
 
     impl TryFrom<Items> for ItemStacks {
         type Error = Error;
@@ -48,17 +170,22 @@ mod format {
             items
                 .0
                 .into_iter()
-                .map(|Item(amt, name)| name.try_into().map(|n| (n, amt)))
-                .collect::<Result<HashMap<_, _>, _>>()
+                .map(|i| ItemStack::try_from(i).map(|ItemStack(n, a)| (n, a)))
+                .collect::<Result<HashMap<_, _>>>()
                 .map(Self::new)
-                .map_err(Into::into)
         }
     }
 
     impl TryFrom<Item> for ItemStack {
         type Error = Error;
 
-        fn try_from(item: Item) -> Result<Self> { Ok(Self(item.1.try_into()?, item.0)) }
+        fn try_from(item: Item) -> Result<Self> {
+            if item.0.unit().is_some() {
+                bail!("Expected a plain amount for {}, got the rate {}", item.1, item.0);
+            }
+
+            Ok(Self(item.1.try_into()?, item.0.amount().clone()))
+        }
     }
 
     impl<'de> Deserialize<'de> for Items {
@@ -81,7 +208,7 @@ mod format {
                 fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Items, A::Error> {
                     let mut vec = vec![];
 
-                    while let Some(s) = seq.next_element::<&str>()? {
+                    while let Some(s) = seq.next_element::<String>()? {
                         vec.push(s.parse().map_err(<A::Error as de::Error>::custom)?);
                     }
 
@@ -93,13 +220,33 @@ mod format {
         }
     }
 
+    impl Serialize for Items {
+        fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+            let items: Vec<_> = self.0.iter().map(ToString::to_string).collect();
+
+            ser.serialize_str(&items.join(" "))
+        }
+    }
+
+    impl fmt::Display for Item {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let amt = self.0.amount().to_exact_string();
+            let volume = if self.0.is_volume() { "m3" } else { "" };
+
+            match self.0.unit() {
+                Some(unit) => write!(f, "{}{}/{} {}", amt, volume, unit, self.1),
+                None => write!(f, "{}{} {}", amt, volume, self.1),
+            }
+        }
+    }
+
     impl std::str::FromStr for Items {
         type Err = Error;
 
         fn from_str(s: &str) -> Result<Self> {
             lazy_static! {
                 static ref ITEM_REGEX: Regex =
-                    Regex::new(r"^\s*([\d.,]+)\s+(\S+)(?:\s+|$)").unwrap();
+                    Regex::new(r"^\s*([\d./]+[a-zA-Z\d³/]*)\s+(\S+)(?:\s+|$)").unwrap();
             }
 
             let mut offs = 0;
@@ -128,7 +275,8 @@ mod format {
 
         fn from_str(s: &str) -> Result<Self> {
             lazy_static! {
-                static ref REGEX: Regex = Regex::new(r"^\s*([\d.,]+)\s+(\S+)\s*$").unwrap();
+                static ref REGEX: Regex =
+                    Regex::new(r"^\s*([\d./]+[a-zA-Z\d³/]*)\s+(\S+)\s*$").unwrap();
             }
 
             let caps = REGEX
@@ -145,11 +293,109 @@ mod format {
     }
 }
 
+/// The game's belt capacities, in items per minute
+const BELTS: [(&str, u64); 6] = [
+    ("mk1", 60),
+    ("mk2", 120),
+    ("mk3", 270),
+    ("mk4", 480),
+    ("mk5", 780),
+    ("mk6", 1200),
+];
+
+/// The game's pipe capacities, in m³ per minute
+const PIPES: [(&str, u64); 2] = [("mk1", 300), ("mk2", 600)];
+
+#[derive(Debug, Error)]
+#[error("Unrecognized conveyor {0:?}, expected belt or pipe")]
+pub struct InvalidConveyor(String);
+
 #[derive(Debug)]
 pub struct Config {
-    raw: HashSet<Item>,
+    raw: HashMap<Item, Resource>,
+    items: HashMap<Item, ItemInfo>,
+    machines: HashMap<Machine, MachineInfo>,
     recipes: Vec<Arc<Recipe>>,
     keyed_recipes: HashMap<Item, Vec<KeyedRecipe>>,
+    logistics: Logistics,
+}
+
+/// What carries items between machines, depending on their form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Conveyor {
+    Belt,
+    Pipe,
+}
+
+/// One tier of belt or pipe and the most it carries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tier {
+    name: String,
+    capacity: Amount,
+}
+
+/// The tiers of belt and pipe available, each sorted slowest first and never
+/// empty
+#[derive(Debug, PartialEq, Eq)]
+pub struct Logistics {
+    belts: Vec<Tier>,
+    pipes: Vec<Tier>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MachineInfo {
+    power: Amount,
+    footprint: Option<(Amount, Amount)>,
+    cost: ItemStacks,
+    inputs: Option<u32>,
+    outputs: Option<u32>,
+}
+
+/// The physical form of an item, which decides how it is transported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Form {
+    #[default]
+    Solid,
+    Liquid,
+    Gas,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ItemInfo {
+    name: Option<String>,
+    form: Form,
+    stack_size: Option<u32>,
+    sink_points: Option<u64>,
+    category: Option<String>,
+}
+
+/// A set of item stacks formatted with the items' display names
+#[derive(Debug)]
+pub struct NamedStacks<'a>(&'a Config, &'a ItemStacks);
+
+/// The richness of a resource node, which scales how fast it can be mined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Purity {
+    Impure,
+    Normal,
+    Pure,
+}
+
+/// The limits on extracting a raw resource.  A resource with neither nodes nor
+/// a limit is treated as unlimited.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Resource {
+    nodes: Vec<(Purity, u32)>,
+    extractors: Vec<(Machine, Amount)>,
+    limit: Option<Amount>,
+}
+
+/// The nodes and extractor needed to mine a raw resource at some rate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extraction {
+    extractor: Machine,
+    nodes: Vec<(Purity, u32)>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -159,6 +405,8 @@ pub struct Recipe {
     time: Amount,
     machine: Machine,
     name: Option<String>,
+    id: Option<RecipeName>,
+    alternate: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -174,14 +422,128 @@ impl Config {
             serde_yaml::from_reader(File::open(from).context("Failed to open config file")?)
                 .context("Failed to parse config file")?;
 
-        Item::registry_ref().register(ret.raw.iter().cloned().chain(
-            ret.recipes.values().flat_map(|r| {
-                r.iter()
-                    .flat_map(|r| r.make.0.iter().map(|format::Item(_, n)| n.clone()))
-            }),
-        ));
+        ret.try_into()
+    }
+
+    /// The raw resources and the limits on extracting each of them
+    pub fn raw(&self) -> &HashMap<Item, Resource> { &self.raw }
+
+    /// Descriptions of the items listed in the config's items section
+    pub fn items(&self) -> &HashMap<Item, ItemInfo> { &self.items }
+
+    /// The display name of an item, falling back to its identifier
+    pub fn item_name(&self, item: Item) -> Cow<'_, str> {
+        match self.items.get(&item).and_then(ItemInfo::name) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(item.to_string()),
+        }
+    }
+
+    /// The physical form of an item, assumed solid if unlisted
+    pub fn item_form(&self, item: Item) -> Form {
+        self.items.get(&item).map_or_else(Form::default, ItemInfo::form)
+    }
+
+    /// Fail if a rate given for `item` is a volume but the item isn't a fluid
+    pub fn check_rate(&self, item: Item, rate: &Rate) -> Result<()> {
+        if !rate.is_volume() {
+            return Ok(());
+        }
+
+        match self.items.get(&item) {
+            Some(info) if info.form().is_fluid() => Ok(()),
+            Some(_) => bail!("{} is solid, so {} can't be a volume of it", item, rate),
+            None => bail!(
+                "{} isn't declared as a fluid in the items section, so {} can't be a volume of it",
+                item,
+                rate
+            ),
+        }
+    }
+
+    pub fn named<'a>(&'a self, stacks: &'a ItemStacks) -> NamedStacks<'a> {
+        NamedStacks(self, stacks)
+    }
+
+    /// Describe a recipe by its name, or by its display-named outputs and
+    /// inputs if it has none
+    pub fn describe(&self, recipe: &Recipe) -> String {
+        match recipe.name {
+            Some(ref name) => name.clone(),
+            None => format!(
+                "{} from {}",
+                self.named(&recipe.outputs),
+                self.named(&recipe.inputs)
+            ),
+        }
+    }
+
+    pub fn machines(&self) -> &HashMap<Machine, MachineInfo> { &self.machines }
+
+    pub fn recipes(&self) -> &[Arc<Recipe>] { &self.recipes }
+
+    pub fn keyed_recipes(&self) -> &HashMap<Item, Vec<KeyedRecipe>> { &self.keyed_recipes }
+
+    /// The tiers of belt and pipe plans may use
+    pub fn logistics(&self) -> &Logistics { &self.logistics }
+}
+
+impl TryFrom<format::Config> for Config {
+    type Error = Error;
+
+    fn try_from(ret: format::Config) -> Result<Self> {
+        check_volumes(&ret)?;
 
-        Machine::registry_ref().register(ret.recipes.keys().cloned());
+        Item::registry_ref().register(
+            ret.raw
+                .iter()
+                .flat_map(format::Raw::names)
+                .chain(ret.items.keys())
+                .cloned()
+                .chain(ret.recipes.values().flat_map(|r| {
+                    r.iter()
+                        .flat_map(|r| r.make.0.iter().map(|format::Item(_, n)| n.clone()))
+                }))
+                .chain(ret.machines.values().flat_map(|m| {
+                    m.cost
+                        .iter()
+                        .flat_map(|c| c.0.iter().map(|format::Item(_, n)| n.clone()))
+                })),
+        );
+
+        Machine::registry_ref().register(ret.machines.keys().cloned());
+
+        for key in ret.recipes.keys() {
+            Machine::new(key).context("Recipes were listed under an undeclared machine")?;
+        }
+
+        let raw = ret
+            .raw
+            .into_iter()
+            .flat_map(|raw| match raw {
+                format::Raw::Unlimited(name) => vec![(name, None)],
+                format::Raw::Limited(map) => map.into_iter().map(|(k, v)| (k, Some(v))).collect(),
+            })
+            .map(|(name, res)| {
+                let res = res
+                    .map_or_else(|| Ok(Resource::default()), Resource::load)
+                    .with_context(|| anyhow!("Invalid resource limits for {}", name))?;
+
+                Ok((Item::new(&name).unwrap(), res))
+            })
+            .collect::<Result<_>>()?;
+
+        let machines = ret
+            .machines
+            .into_iter()
+            .map(|(name, machine)| Ok((name.try_into().unwrap(), machine.try_into()?)))
+            .collect::<Result<_>>()?;
+
+        RecipeName::registry_ref().register(
+            ret.recipes
+                .values()
+                .flat_map(|r| r.iter().filter_map(|r| r.name.as_deref().map(recipe_key))),
+        );
 
         let recipes: Vec<Arc<Recipe>> = ret
             .recipes
@@ -189,25 +551,25 @@ impl Config {
             .flat_map(|(k, v)| {
                 v.into_iter()
                     .zip(std::iter::repeat(k))
-                    .map(|(recipe, machine)| {
-                        Ok(Recipe {
-                            inputs: recipe.from.try_into()?,
-                            outputs: recipe.make.try_into().unwrap(),
-                            time: recipe.in_sec,
-                            machine: machine.try_into().unwrap(),
-                            name: recipe.name,
-                        })
-                    })
+                    .map(|(recipe, machine)| Recipe::load(machine.try_into().unwrap(), recipe))
             })
             .map(|r| r.map(Arc::new))
             .collect::<Result<_>>()?;
 
+        let mut ids = HashSet::default();
+
+        for id in recipes.iter().filter_map(|r| r.id) {
+            if !ids.insert(id) {
+                bail!("Multiple recipes are named {}", id);
+            }
+        }
+
         let keyed_recipes: HashMap<Item, Vec<KeyedRecipe>> = recipes
             .iter()
             .flat_map(|recipe| {
                 recipe.outputs.stacks_iter().map(|stack| {
                     (stack.0, KeyedRecipe {
-                        amount: stack.1,
+                        amount: stack.1.clone(),
                         extra_outs: recipe.outputs.clone() - stack,
                         recipe: recipe.clone(),
                     })
@@ -218,15 +580,639 @@ impl Config {
                 h
             });
 
+        let items = ret
+            .items
+            .into_iter()
+            .map(|(name, info)| {
+                (Item::new(&name).unwrap(), ItemInfo {
+                    name: info.name,
+                    form: info.form,
+                    stack_size: info.stack_size,
+                    sink_points: info.sink_points,
+                    category: info.category,
+                })
+            })
+            .collect();
+
+        let logistics = Logistics::load(ret.logistics).context("Invalid logistics section")?;
+
         Ok(Config {
-            raw: ret
-                .raw
-                .into_iter()
-                .map(|s| Item::new(&s))
-                .collect::<Result<_, _>>()
-                .unwrap(),
+            raw,
+            items,
+            machines,
             recipes,
             keyed_recipes,
+            logistics,
+        })
+    }
+}
+
+/// Make sure only fluids are measured in cubic metres, since a volume given
+/// for a solid item is almost certainly a mistake.  Items not described in the
+/// items section are taken to be solid.
+fn check_volumes(config: &format::Config) -> Result<()> {
+    let recipes = config.recipes.values().flatten().flat_map(|r| {
+        r.make
+            .0
+            .iter()
+            .chain(&r.from.0)
+            .map(|format::Item(rate, name)| (rate, name))
+    });
+
+    let raw = config.raw.iter().flat_map(|raw| match raw {
+        format::Raw::Unlimited(_) => vec![],
+        format::Raw::Limited(map) => map
+            .iter()
+            .flat_map(|(name, res)| {
+                res.extractors
+                    .values()
+                    .chain(&res.limit)
+                    .map(move |rate| (rate, name))
+            })
+            .collect(),
+    });
+
+    for (rate, name) in recipes.chain(raw).filter(|(r, _)| r.is_volume()) {
+        match config.items.get(name) {
+            Some(info) if info.form.is_fluid() => (),
+            Some(_) => bail!("{} is solid, so {} can't be a volume of it", name, rate),
+            None => bail!(
+                "{} isn't declared as a fluid in the items section, so {} can't be a volume of it",
+                name,
+                rate
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+impl Recipe {
+    fn load(machine: Machine, recipe: format::Recipe) -> Result<Self> {
+        // Recipes given entirely as rates may omit the cycle duration, in
+        // which case one cycle is one minute
+        let time = match recipe.in_sec {
+            Some(t) => t,
+            None if recipe.make.has_rates() || recipe.from.has_rates() => {
+                Amount::from_integer(60)
+            },
+            None => bail!(
+                "Recipe for {:?} needs a duration unless its amounts are rates",
+                recipe.make
+            ),
+        };
+
+        if time.is_zero() {
+            bail!("Recipe for {:?} has a duration of zero", recipe.make);
+        }
+
+        if recipe.alternate && recipe.name.is_none() {
+            bail!("Alternate recipe for {:?} must have a name", recipe.make);
+        }
+
+        Ok(Self {
+            inputs: recipe.from.into_stacks(&time)?,
+            outputs: recipe.make.into_stacks(&time)?,
+            time,
+            machine,
+            id: recipe
+                .name
+                .as_deref()
+                .map(|n| RecipeName::new(&recipe_key(n)).unwrap()),
+            name: recipe.name,
+            alternate: recipe.alternate,
         })
     }
+
+    pub fn inputs(&self) -> &ItemStacks { &self.inputs }
+
+    pub fn outputs(&self) -> &ItemStacks { &self.outputs }
+
+    pub fn machine(&self) -> Machine { self.machine }
+
+    /// The identifier derived from this recipe's name, if it has one
+    pub fn id(&self) -> Option<RecipeName> { self.id }
+
+    pub fn alternate(&self) -> bool { self.alternate }
+
+    /// The number of cycles one machine completes per minute
+    pub fn cycles_per_min(&self) -> Amount { Amount::from_integer(60) / &self.time }
+}
+
+impl TryFrom<format::Machine> for MachineInfo {
+    type Error = Error;
+
+    fn try_from(machine: format::Machine) -> Result<Self> {
+        Ok(Self {
+            power: machine.power,
+            footprint: machine.footprint.map(|f| (f.width, f.length)),
+            cost: machine
+                .cost
+                .map_or_else(|| Ok(ItemStacks::empty()), TryInto::try_into)?,
+            inputs: machine.inputs,
+            outputs: machine.outputs,
+        })
+    }
+}
+
+impl MachineInfo {
+    /// The power drawn by one machine running at full speed, in MW
+    pub fn power(&self) -> &Amount { &self.power }
+
+    /// The width and length of the machine, in metres
+    pub fn footprint(&self) -> Option<&(Amount, Amount)> { self.footprint.as_ref() }
+
+    /// The items needed to build one machine
+    pub fn cost(&self) -> &ItemStacks { &self.cost }
+
+    /// The number of input and output ports, if known
+    pub fn ports(&self) -> (Option<u32>, Option<u32>) { (self.inputs, self.outputs) }
+}
+
+impl Form {
+    /// Whether items of this form travel through pipes rather than on belts
+    pub fn is_fluid(self) -> bool { self != Self::Solid }
+
+    /// The suffix shown after amounts of this form, so fluids read in m³
+    pub fn unit(self) -> &'static str {
+        if self.is_fluid() {
+            " m³"
+        } else {
+            ""
+        }
+    }
+
+    /// What carries items of this form
+    pub fn conveyor(self) -> Conveyor {
+        if self.is_fluid() {
+            Conveyor::Pipe
+        } else {
+            Conveyor::Belt
+        }
+    }
+}
+
+impl fmt::Display for Form {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Solid => "solid",
+            Self::Liquid => "liquid",
+            Self::Gas => "gas",
+        })
+    }
+}
+
+impl ItemInfo {
+    /// The name shown in game, if known
+    pub fn name(&self) -> Option<&str> { self.name.as_deref() }
+
+    pub fn form(&self) -> Form { self.form }
+
+    /// The most of this item that fits in one inventory slot, if known
+    pub fn stack_size(&self) -> Option<u32> { self.stack_size }
+
+    /// The points awarded for sinking one of this item, if known
+    pub fn sink_points(&self) -> Option<u64> { self.sink_points }
+
+    pub fn category(&self) -> Option<&str> { self.category.as_deref() }
+}
+
+impl fmt::Display for NamedStacks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self(config, stacks) = *self;
+        let mut stacks: Vec<_> = stacks
+            .iter()
+            .map(|(i, a)| (config.item_name(*i), config.item_form(*i), a))
+            .collect();
+        stacks.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+        stacks.into_iter().enumerate().try_for_each(|(i, (name, form, amt))| {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}{} {}", amt, form.unit(), name)
+        })
+    }
+}
+
+impl Purity {
+    /// The extraction rate of this node relative to a normal node
+    pub fn multiplier(self) -> Amount {
+        match self {
+            Self::Impure => Amount::from_integer(1) / Amount::from_integer(2),
+            Self::Normal => Amount::from_integer(1),
+            Self::Pure => Amount::from_integer(2),
+        }
+    }
+}
+
+impl fmt::Display for Purity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Impure => "impure",
+            Self::Normal => "normal",
+            Self::Pure => "pure",
+        })
+    }
+}
+
+impl Resource {
+    fn load(res: format::Resource) -> Result<Self> {
+        let format::Nodes {
+            impure,
+            normal,
+            pure,
+        } = res.nodes;
+
+        // Richest first, so extractions fill the fewest nodes
+        let nodes: Vec<_> = [
+            (Purity::Pure, pure),
+            (Purity::Normal, normal),
+            (Purity::Impure, impure),
+        ]
+        .into_iter()
+        .filter(|(_, n)| *n > 0)
+        .collect();
+
+        if !nodes.is_empty() && res.extractors.is_empty() {
+            bail!("Resource nodes were listed without any extractors");
+        }
+
+        let mut extractors = res
+            .extractors
+            .into_iter()
+            .map(|(name, rate)| {
+                let machine = Machine::new(&name)
+                    .context("Extractors must be declared in the machines section")?;
+
+                Ok((machine, rate.per_min_or(TimeUnit::Minute)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Slowest first, so the lowest sufficient tier is found first
+        extractors.sort_by_cached_key(|(m, r)| (r.clone(), m.to_string()));
+
+        Ok(Self {
+            nodes,
+            extractors,
+            limit: res.limit.map(|l| l.per_min_or(TimeUnit::Minute)),
+        })
+    }
+
+    /// The available nodes of each purity, richest first
+    pub fn nodes(&self) -> &[(Purity, u32)] { &self.nodes }
+
+    /// The available extractors and their rates on a normal node, in items
+    /// per minute, slowest first
+    pub fn extractors(&self) -> &[(Machine, Amount)] { &self.extractors }
+
+    /// The most that can be extracted from every node using an extractor of
+    /// the given rate on a normal node
+    fn capacity(&self, rate: &Amount) -> Amount {
+        self.nodes.iter().fold(Amount::zero(), |s, (p, n)| {
+            s + rate * p.multiplier() * Amount::from_integer((*n).into())
+        })
+    }
+
+    /// The highest rate, in items per minute, this resource can be extracted
+    /// at, or `None` if it is unlimited
+    pub fn cap(&self) -> Option<Amount> {
+        let nodes = self
+            .extractors
+            .last()
+            .filter(|_| !self.nodes.is_empty())
+            .map(|(_, r)| self.capacity(r));
+
+        match (nodes, self.limit.clone()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// The slowest extractor that can meet `rate` items per minute, and the
+    /// fewest nodes it needs, or `None` if this resource has no nodes or
+    /// can't meet the rate
+    pub fn extraction(&self, rate: &Amount) -> Option<Extraction> {
+        let (extractor, base) = self
+            .extractors
+            .iter()
+            .find(|(_, r)| self.capacity(r) >= *rate)?;
+
+        let mut left = rate.clone();
+        let mut nodes = vec![];
+
+        for (purity, count) in &self.nodes {
+            if left.is_zero() {
+                break;
+            }
+
+            let each = base * purity.multiplier();
+            let used = (&left / &each)
+                .ceil()
+                .as_ratio()
+                .to_integer()
+                .to_u32()
+                .map_or(*count, |u| u.min(*count));
+
+            left = left
+                .checked_sub(&(each * Amount::from_integer(used.into())))
+                .unwrap_or_else(Amount::zero);
+            nodes.push((*purity, used));
+        }
+
+        Some(Extraction {
+            extractor: *extractor,
+            nodes,
+        })
+    }
+}
+
+impl Extraction {
+    pub fn extractor(&self) -> Machine { self.extractor }
+
+    /// The number of nodes used of each purity, richest first
+    pub fn nodes(&self) -> &[(Purity, u32)] { &self.nodes }
+}
+
+impl fmt::Display for Extraction {
+    /// Formats the nodes used, e.g. `2 pure, 1 normal with miner_mk2`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (purity, count)) in self.nodes.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{} {}", count, purity)?;
+        }
+
+        write!(f, " with {}", self.extractor)
+    }
+}
+
+impl fmt::Display for Conveyor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Belt => "belt",
+            Self::Pipe => "pipe",
+        })
+    }
+}
+
+impl FromStr for Conveyor {
+    type Err = InvalidConveyor;
+
+    fn from_str(s: &str) -> Result<Self, InvalidConveyor> {
+        Ok(match s {
+            "belt" | "belts" | "conveyor" => Self::Belt,
+            "pipe" | "pipes" | "pipeline" => Self::Pipe,
+            s => return Err(InvalidConveyor(s.to_owned())),
+        })
+    }
+}
+
+impl ser::Serialize for Conveyor {
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.collect_str(self)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Conveyor {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let s: String = de::Deserialize::deserialize(de)?;
+
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Tier {
+    pub fn name(&self) -> &str { &self.name }
+
+    /// The most one line of this tier carries, per minute
+    pub fn capacity(&self) -> &Amount { &self.capacity }
+}
+
+impl Logistics {
+    fn load(logistics: Option<format::Logistics>) -> Result<Self> {
+        let format::Logistics { belts, pipes } = logistics.unwrap_or_default();
+
+        Ok(Self {
+            belts: Self::load_tiers(belts, &BELTS).context("Invalid belt tiers")?,
+            pipes: Self::load_tiers(pipes, &PIPES).context("Invalid pipe tiers")?,
+        })
+    }
+
+    /// Load one table of tiers, falling back to the game's if it is empty
+    fn load_tiers(table: BTreeMap<String, Rate>, default: &[(&str, u64)]) -> Result<Vec<Tier>> {
+        let mut tiers = if table.is_empty() {
+            default
+                .iter()
+                .map(|(name, capacity)| Tier {
+                    name: (*name).to_owned(),
+                    capacity: Amount::from_integer(*capacity),
+                })
+                .collect()
+        } else {
+            table
+                .into_iter()
+                .map(|(name, rate)| {
+                    let capacity = rate.per_min_or(TimeUnit::Minute);
+
+                    if capacity.is_zero() {
+                        bail!("Tier {} has a capacity of zero", name);
+                    }
+
+                    Ok(Tier { name, capacity })
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        tiers.sort_by(|a, b| a.capacity.cmp(&b.capacity));
+
+        Ok(tiers)
+    }
+
+    /// Every tier of a conveyor, slowest first
+    pub fn tiers(&self, conveyor: Conveyor) -> &[Tier] {
+        match conveyor {
+            Conveyor::Belt => &self.belts,
+            Conveyor::Pipe => &self.pipes,
+        }
+    }
+
+    pub fn tier(&self, conveyor: Conveyor, name: &str) -> Option<&Tier> {
+        self.tiers(conveyor).iter().find(|t| t.name == name)
+    }
+
+    /// The tiers of a conveyor up to and including `max`, or all of them if no
+    /// highest tier is given
+    pub fn unlocked(&self, conveyor: Conveyor, max: Option<&str>) -> &[Tier] {
+        let tiers = self.tiers(conveyor);
+
+        match max.and_then(|m| tiers.iter().position(|t| t.name == m)) {
+            Some(i) => &tiers[..=i],
+            None => tiers,
+        }
+    }
+}
+
+impl KeyedRecipe {
+    pub fn recipe(&self) -> &Arc<Recipe> { &self.recipe }
+
+    /// Everything else the recipe makes alongside the item it is keyed by
+    pub fn extra_outs(&self) -> &ItemStacks { &self.extra_outs }
+}
+
+impl fmt::Display for Recipe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(ref name) => f.write_str(name),
+            None => write!(f, "{} from {}", self.outputs, self.inputs),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Load a config from YAML, as though read from a file
+    pub fn load(yaml: &str) -> Result<Config> {
+        serde_yaml::from_str::<format::Config>(yaml)?.try_into()
+    }
+
+    #[test]
+    fn fluid_volumes() {
+        let yaml = |items: &str| {
+            format!(
+                "
+raw: [water, empty_canister]
+items: {{ {} }}
+machines:
+  packager: {{ power: 10 }}
+recipes:
+  packager:
+    - {{ make: 1 packaged_water, from: 500l water 1 empty_canister, in: 1 }}
+",
+                items
+            )
+        };
+
+        let config = load(&yaml("water: { form: liquid }")).unwrap();
+        let water = Item::new("water").unwrap();
+        assert_eq!(config.recipes()[0].inputs()[&water], "0.5".parse().unwrap());
+
+        let err = load(&yaml("water: { form: solid }")).unwrap_err();
+        assert_eq!(err.to_string(), "water is solid, so 0.5m3 can't be a volume of it");
+
+        let err = load(&yaml("")).unwrap_err();
+        assert!(err.to_string().starts_with("water isn't declared as a fluid"), "{}", err);
+
+        let volume = "1m3".parse().unwrap();
+        let canister = Item::new("empty_canister").unwrap();
+        assert!(config.check_rate(water, &volume).is_ok());
+
+        let err = config.check_rate(canister, &volume).unwrap_err();
+        assert!(err.to_string().starts_with("empty_canister isn't declared as a fluid"), "{}", err);
+    }
+
+    #[test]
+    fn resource_nodes() {
+        let config = load(
+            "
+raw:
+  - iron_ore:
+      nodes: { impure: 2, normal: 1, pure: 1 }
+      extractors: { miner_mk1: 60, miner_mk2: 120 }
+  - coal: { nodes: { normal: 1 }, extractors: { miner_mk1: 60 }, limit: 30 }
+  - sulfur: { limit: 100 }
+  - water
+machines:
+  miner_mk1: { power: 5 }
+  miner_mk2: { power: 12 }
+recipes: {}
+",
+        )
+        .unwrap();
+        let resource = |name: &str| &config.raw()[&Item::new(name).unwrap()];
+        let amount = |s: &str| s.parse::<Amount>().unwrap();
+        let extraction = |rate: &str| {
+            let ext = resource("iron_ore").extraction(&amount(rate))?;
+
+            Some((ext.extractor().to_string(), ext.nodes().to_vec()))
+        };
+
+        // The slowest miner that can keep up, filling the richest nodes first
+        assert_eq!(
+            extraction("150"),
+            Some(("miner_mk1".into(), vec![(Purity::Pure, 1), (Purity::Normal, 1)]))
+        );
+        assert_eq!(
+            extraction("450"),
+            Some(("miner_mk2".into(), vec![
+                (Purity::Pure, 1),
+                (Purity::Normal, 1),
+                (Purity::Impure, 2),
+            ]))
+        );
+
+        // Demand beyond every node can't be met, and caps the resource
+        assert_eq!(extraction("481"), None);
+        assert_eq!(resource("iron_ore").cap(), Some(amount("480")));
+        assert_eq!(resource("coal").cap(), Some(amount("30")));
+
+        // Resources without nodes are only capped by their limit, if any
+        assert_eq!(resource("sulfur").cap(), Some(amount("100")));
+        assert!(resource("sulfur").extraction(&amount("10")).is_none());
+        assert_eq!(resource("water").cap(), None);
+        assert!(resource("water").extraction(&amount("10")).is_none());
+    }
+
+    #[test]
+    fn item_metadata() {
+        let config = load(
+            "
+raw: [water, ore]
+items:
+  water: { form: liquid, category: fluids }
+  ingot: { name: Metal Ingot, stack_size: 100, sink_points: 6 }
+machines:
+  smelter: { power: 4 }
+recipes:
+  smelter:
+    - { make: 1 ingot, from: 1 ore, in: 2 }
+",
+        )
+        .unwrap();
+        let item = |name: &str| Item::new(name).unwrap();
+        let ingot = &config.items()[&item("ingot")];
+
+        assert_eq!(config.item_name(item("ingot")), "Metal Ingot");
+        assert_eq!((ingot.stack_size(), ingot.sink_points()), (Some(100), Some(6)));
+        assert_eq!(config.items()[&item("water")].category(), Some("fluids"));
+
+        // Unlisted items are named by their identifier and assumed solid
+        assert_eq!(config.item_name(item("ore")), "ore");
+        assert_eq!(config.item_form(item("ore")), Form::Solid);
+        assert_eq!(config.item_form(item("ore")).conveyor(), Conveyor::Belt);
+        assert_eq!(config.item_form(item("water")).conveyor(), Conveyor::Pipe);
+    }
+
+    #[test]
+    fn undeclared_machine() {
+        let err = load(
+            "
+raw: [ore]
+machines:
+  smelter: { power: 4 }
+recipes:
+  undeclared_forge:
+    - { make: 1 ingot, from: 1 ore, in: 2 }
+",
+        )
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "Recipes were listed under an undeclared machine");
+        assert!(err.root_cause().to_string().contains("undeclared_forge"), "{:?}", err);
+    }
 }
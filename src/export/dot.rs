@@ -0,0 +1,100 @@
+use std::{fmt, fmt::Write};
+
+use super::{clusters, edge_label, ends, node_id, node_label};
+use crate::{
+    config::Config,
+    items::TimeUnit,
+    plan::{Node, Plan},
+};
+
+/// Quote a string for use as a DOT identifier or label, joining lines with
+/// centred line breaks
+fn quote(lines: &[String]) -> String {
+    let lines: Vec<_> = lines
+        .iter()
+        .map(|l| l.replace('\\', "\\\\").replace('"', "\\\""))
+        .collect();
+
+    format!("\"{}\"", lines.join("\\n"))
+}
+
+fn write_node(
+    f: &mut String,
+    indent: &str,
+    plan: &Plan,
+    config: &Config,
+    node: Node,
+) -> fmt::Result {
+    let shape = match node {
+        Node::Raw(_) | Node::Available(_) => "invhouse",
+        Node::Recipe(_) => "box",
+        Node::Output(_) => "house",
+        Node::Surplus(_) | Node::Sink(_) => "note",
+    };
+
+    writeln!(
+        f,
+        "{}{} [label={}, shape={}];",
+        indent,
+        quote(&[node_id(node)]),
+        quote(&node_label(plan, config, node)),
+        shape
+    )
+}
+
+/// Render a plan as a Graphviz graph, with the recipes run in each type of
+/// machine clustered together
+pub fn render(plan: &Plan, config: &Config, unit: TimeUnit) -> Result<String, fmt::Error> {
+    let mut f = String::new();
+
+    writeln!(f, "digraph plan {{")?;
+    writeln!(f, "  rankdir=LR;")?;
+    writeln!(f, "  node [fontname=\"sans-serif\"];")?;
+    writeln!(f, "  edge [fontname=\"sans-serif\", fontsize=10];")?;
+
+    for (machine, recipes) in clusters(plan) {
+        writeln!(f)?;
+        writeln!(f, "  subgraph {} {{", quote(&[format!("cluster_{}", machine)]))?;
+        writeln!(f, "    label={};", quote(&[machine]))?;
+        writeln!(f, "    style=rounded;")?;
+
+        for i in recipes {
+            write_node(&mut f, "    ", plan, config, Node::Recipe(i))?;
+        }
+
+        writeln!(f, "  }}")?;
+    }
+
+    let ends = ends(plan);
+
+    if !ends.is_empty() {
+        writeln!(f)?;
+    }
+
+    for node in ends {
+        write_node(&mut f, "  ", plan, config, node)?;
+    }
+
+    if !plan.edges().is_empty() {
+        writeln!(f)?;
+    }
+
+    for edge in plan.edges() {
+        writeln!(
+            f,
+            "  {} -> {} [label={}{}];",
+            quote(&[node_id(edge.from())]),
+            quote(&[node_id(edge.to())]),
+            quote(&edge_label(config, edge, unit)),
+            if edge.lines().is_over() {
+                ", color=red, fontcolor=red"
+            } else {
+                ""
+            }
+        )?;
+    }
+
+    writeln!(f, "}}")?;
+
+    Ok(f)
+}
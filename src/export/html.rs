@@ -0,0 +1,350 @@
+use std::{collections::BTreeMap, fmt, fmt::Write};
+
+use num_traits::Zero;
+
+use super::{edge_label, ends, layout, node_id, node_label};
+use crate::{
+    config::Config,
+    items::{Amount, Item, TimeUnit},
+    plan::{Edge, Node, Plan},
+    HashMap,
+};
+
+/// The rough width of one character of label text, in pixels
+const CHAR_WIDTH: usize = 7;
+const LINE_HEIGHT: usize = 16;
+const PADDING: usize = 10;
+const COLUMN_GAP: usize = 120;
+const ROW_GAP: usize = 24;
+const MARGIN: usize = 20;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 0.5em 0; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }
+summary { cursor: pointer; padding: 0.25em 0; }
+svg { max-width: 100%; height: auto; }
+.node rect { fill: #eef2fa; stroke: #4a5a80; }
+.node.source rect { fill: #e8f5e9; stroke: #3b7a3f; }
+.node.sink rect { fill: #fff3e0; stroke: #a86a12; }
+.node text { font-size: 12px; }
+.edge { fill: none; stroke: #8a8a8a; stroke-width: 1.5; }
+.edge.over { stroke: #d32f2f; }
+.edge:hover { stroke-width: 4; }
+.over-note { color: #d32f2f; }
+";
+
+/// Escape text for use in HTML or SVG
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A node of the graph, with its label and position
+struct Placed {
+    node: Node,
+    label: Vec<String>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Lay out every node of a plan's graph in columns, returning the nodes and
+/// the size of the drawing, which is just its margins for an empty plan
+fn place(plan: &Plan, config: &Config) -> (Vec<Placed>, usize, usize) {
+    let nodes: Vec<_> = (0..plan.recipes().len())
+        .map(Node::Recipe)
+        .chain(ends(plan))
+        .collect();
+
+    if nodes.is_empty() {
+        return (vec![], 2 * MARGIN, 2 * MARGIN);
+    }
+
+    let index: HashMap<_, _> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let edges: Vec<_> = plan
+        .edges()
+        .iter()
+        .map(|e| (index[&e.from()], index[&e.to()]))
+        .collect();
+
+    let mut placed: Vec<Option<Placed>> = nodes.iter().map(|_| None).collect();
+    let mut x = MARGIN;
+    let mut bottom = 0;
+
+    for layer in layout::layers(nodes.len(), &edges) {
+        let labels: Vec<_> = layer
+            .iter()
+            .map(|i| node_label(plan, config, nodes[*i]))
+            .collect();
+        let width = labels
+            .iter()
+            .flatten()
+            .map(|l| l.chars().count() * CHAR_WIDTH + 2 * PADDING)
+            .max()
+            .unwrap_or(0);
+        let mut y = MARGIN;
+
+        for (i, label) in layer.into_iter().zip(labels) {
+            let height = label.len() * LINE_HEIGHT + 2 * PADDING;
+
+            placed[i] = Some(Placed {
+                node: nodes[i],
+                label,
+                x,
+                y,
+                width,
+                height,
+            });
+            y += height + ROW_GAP;
+        }
+
+        bottom = bottom.max(y);
+        x += width + COLUMN_GAP;
+    }
+
+    let placed = placed.into_iter().flatten().collect();
+
+    (placed, x - COLUMN_GAP + MARGIN, bottom - ROW_GAP + MARGIN)
+}
+
+fn edge_title(plan: &Plan, config: &Config, edge: &Edge, unit: TimeUnit) -> String {
+    let label = edge_label(config, edge, unit);
+
+    format!(
+        "{}: {} from {} to {}, on {}{}",
+        label[0],
+        label[1],
+        plan.node_name(config, edge.from()),
+        plan.node_name(config, edge.to()),
+        label[2],
+        if edge.lines().is_over() {
+            " (exceeds the highest unlocked tier)"
+        } else {
+            ""
+        }
+    )
+}
+
+fn write_svg(f: &mut String, plan: &Plan, config: &Config, unit: TimeUnit) -> fmt::Result {
+    let (placed, width, height) = place(plan, config);
+    let find = |node: Node| placed.iter().find(|p| p.node == node);
+
+    writeln!(
+        f,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\">",
+        width, height
+    )?;
+    writeln!(
+        f,
+        "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+         markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\">\
+         <path d=\"M0,0 L10,5 L0,10 z\" fill=\"#8a8a8a\"/></marker></defs>"
+    )?;
+
+    for edge in plan.edges() {
+        let (from, to) = match (find(edge.from()), find(edge.to())) {
+            (Some(from), Some(to)) => (from, to),
+            _ => continue,
+        };
+
+        let (x1, y1) = (from.x + from.width, from.y + from.height / 2);
+        let (x2, y2) = (to.x, to.y + to.height / 2);
+
+        writeln!(
+            f,
+            "<path class=\"edge{}\" d=\"M{},{} C{},{} {},{} {},{}\" \
+             marker-end=\"url(#arrow)\"><title>{}</title></path>",
+            if edge.lines().is_over() { " over" } else { "" },
+            x1,
+            y1,
+            x1 + COLUMN_GAP / 2,
+            y1,
+            x2.saturating_sub(COLUMN_GAP / 2),
+            y2,
+            x2,
+            y2,
+            escape(&edge_title(plan, config, edge, unit))
+        )?;
+    }
+
+    for p in &placed {
+        let class = match p.node {
+            Node::Raw(_) | Node::Available(_) => "node source",
+            Node::Recipe(_) => "node",
+            Node::Output(_) | Node::Surplus(_) | Node::Sink(_) => "node sink",
+        };
+
+        let mut title = p.label.clone();
+        title.extend(
+            plan.edges()
+                .iter()
+                .filter(|e| e.from() == p.node || e.to() == p.node)
+                .map(|e| {
+                    let label = edge_label(config, e, unit);
+                    let dir = if e.to() == p.node { "in" } else { "out" };
+
+                    format!("{} {}: {}", dir, label[0], label[1])
+                }),
+        );
+
+        writeln!(
+            f,
+            "<g class=\"{}\" id=\"{}\"><title>{}</title>",
+            class,
+            node_id(p.node),
+            escape(&title.join("\n"))
+        )?;
+        writeln!(
+            f,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\"/>",
+            p.x, p.y, p.width, p.height
+        )?;
+        write!(f, "<text>")?;
+
+        for (i, line) in p.label.iter().enumerate() {
+            write!(
+                f,
+                "<tspan x=\"{}\" y=\"{}\">{}</tspan>",
+                p.x + PADDING,
+                p.y + PADDING + (i + 1) * LINE_HEIGHT - 4,
+                escape(line)
+            )?;
+        }
+
+        writeln!(f, "</text></g>")?;
+    }
+
+    writeln!(f, "</svg>")
+}
+
+/// Write a collapsible section for each item listing every edge carrying it
+fn write_items(f: &mut String, plan: &Plan, config: &Config, unit: TimeUnit) -> fmt::Result {
+    let mut items: BTreeMap<String, (Item, Vec<&Edge>)> = BTreeMap::new();
+
+    for edge in plan.edges() {
+        items
+            .entry(config.item_name(edge.item()).into_owned())
+            .or_insert_with(|| (edge.item(), vec![]))
+            .1
+            .push(edge);
+    }
+
+    writeln!(f, "<h2>Items</h2>")?;
+
+    for (name, (item, edges)) in items {
+        let total = edges.iter().fold(Amount::zero(), |s, e| s + e.rate());
+        let form = config.item_form(item);
+
+        writeln!(
+            f,
+            "<details><summary>{} &mdash; {}{}/{}</summary>",
+            escape(&name),
+            unit.convert(&total),
+            form.unit(),
+            unit
+        )?;
+        writeln!(
+            f,
+            "<table><tr><th>From</th><th>To</th><th>Rate</th><th>Carried on</th></tr>"
+        )?;
+
+        for edge in edges {
+            writeln!(
+                f,
+                "<tr><td>{}</td><td>{}</td><td>{}{}/{}</td><td>{}{}</td></tr>",
+                escape(&plan.node_name(config, edge.from())),
+                escape(&plan.node_name(config, edge.to())),
+                unit.convert(edge.rate()),
+                form.unit(),
+                unit,
+                edge.lines(),
+                if edge.lines().is_over() {
+                    " <span class=\"over-note\">(exceeds the highest unlocked tier)</span>"
+                } else {
+                    ""
+                }
+            )?;
+        }
+
+        writeln!(f, "</table></details>")?;
+    }
+
+    Ok(())
+}
+
+fn write_machines(f: &mut String, plan: &Plan) -> fmt::Result {
+    let mut machines: Vec<_> = plan.machines().iter().collect();
+    machines.sort_by_cached_key(|(m, _)| m.to_string());
+
+    writeln!(f, "<h2>Machines</h2>")?;
+    writeln!(f, "<table><tr><th>Machine</th><th>Running</th><th>Power (MW)</th></tr>")?;
+
+    for (machine, count) in machines {
+        writeln!(
+            f,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&machine.to_string()),
+            count,
+            plan.power().get(machine).map_or_else(String::new, ToString::to_string)
+        )?;
+    }
+
+    writeln!(
+        f,
+        "<tr><th>Total</th><td></td><th>{}</th></tr></table>",
+        plan.total_power()
+    )
+}
+
+/// Render a plan as a self-contained HTML page, drawing its graph as an
+/// inline SVG so the page needs nothing else to display
+pub fn render(plan: &Plan, config: &Config, unit: TimeUnit) -> Result<String, fmt::Error> {
+    let mut f = String::new();
+
+    writeln!(f, "<!DOCTYPE html>")?;
+    writeln!(f, "<html lang=\"en\">")?;
+    writeln!(f, "<head>")?;
+    writeln!(f, "<meta charset=\"utf-8\">")?;
+    writeln!(f, "<title>Production plan</title>")?;
+    writeln!(f, "<style>\n{}</style>", STYLE)?;
+    writeln!(f, "</head>")?;
+    writeln!(f, "<body>")?;
+    writeln!(f, "<h1>Production plan</h1>")?;
+    writeln!(f, "<p>Rates are given per {}. Hover over the graph for details.</p>", unit)?;
+    write_svg(&mut f, plan, config, unit)?;
+    write_items(&mut f, plan, config, unit)?;
+    write_machines(&mut f, plan)?;
+    writeln!(f, "</body>")?;
+    writeln!(f, "</html>")?;
+
+    Ok(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::tests::load,
+        items::ItemStacks,
+        plan::{Objective, Settings},
+    };
+
+    #[test]
+    fn empty_plan() {
+        let config = load("{ raw: [], machines: {}, recipes: {} }").unwrap();
+        let none = ItemStacks::empty();
+        let plan =
+            Plan::calculate(&config, &none, &none, &Settings::default(), Objective::Raw).unwrap();
+
+        let (placed, width, height) = place(&plan, &config);
+
+        assert!(placed.is_empty());
+        assert_eq!((width, height), (2 * MARGIN, 2 * MARGIN));
+        assert!(render(&plan, &config, TimeUnit::Minute).is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+/// How many times columns are reordered towards their neighbours
+const SWEEPS: usize = 4;
+
+/// Split the nodes `0..count` of a graph into ordered layers, so that every
+/// edge not part of a cycle points from an earlier layer to a later one, and
+/// order each layer to keep edges short
+pub fn layers(count: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let forward = acyclic(count, edges);
+    let mut layer = vec![0_usize; count];
+
+    // Longest-path layering, visiting nodes in topological order
+    for &node in &topological(count, &forward) {
+        for &(from, to) in &forward {
+            if from == node {
+                layer[to] = layer[to].max(layer[from] + 1);
+            }
+        }
+    }
+
+    let mut layers = vec![vec![]; layer.iter().max().map_or(0, |l| l + 1)];
+
+    for (node, &l) in layer.iter().enumerate() {
+        layers[l].push(node);
+    }
+
+    for sweep in 0..SWEEPS {
+        if sweep % 2 == 0 {
+            for l in 1..layers.len() {
+                reorder(&mut layers, l, l - 1, &forward, |(from, to)| (to, from));
+            }
+        } else {
+            for l in (0..layers.len().saturating_sub(1)).rev() {
+                reorder(&mut layers, l, l + 1, &forward, |e| e);
+            }
+        }
+    }
+
+    layers
+}
+
+/// The edges of a graph without those closing a cycle, found by a depth-first
+/// search from each node in order.  The search keeps its own stack, since
+/// recipe graphs can be deep.
+fn acyclic(count: usize, edges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        New,
+        Open,
+        Done,
+    }
+
+    let mut marks = vec![Mark::New; count];
+    let mut out = vec![];
+    // Each open node, with the index of the next edge to look at from it
+    let mut path = vec![];
+
+    for root in 0..count {
+        if marks[root] != Mark::New {
+            continue;
+        }
+
+        marks[root] = Mark::Open;
+        path.push((root, 0));
+
+        while let Some((node, next)) = path.pop() {
+            if let Some(i) = (next..edges.len()).find(|i| edges[*i].0 == node) {
+                let to = edges[i].1;
+
+                path.push((node, i + 1));
+
+                match marks[to] {
+                    Mark::New => {
+                        out.push((node, to));
+                        marks[to] = Mark::Open;
+                        path.push((to, 0));
+                    },
+                    Mark::Done => out.push((node, to)),
+                    Mark::Open => (),
+                }
+            } else {
+                marks[node] = Mark::Done;
+            }
+        }
+    }
+
+    out
+}
+
+/// Sort the nodes of an acyclic graph so every edge points forwards
+fn topological(count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut incoming = vec![0_usize; count];
+
+    for &(_, to) in edges {
+        incoming[to] += 1;
+    }
+
+    let mut ready: Vec<_> = (0..count).filter(|n| incoming[*n] == 0).rev().collect();
+    let mut order = vec![];
+
+    while let Some(node) = ready.pop() {
+        order.push(node);
+
+        for &(from, to) in edges {
+            if from == node {
+                incoming[to] -= 1;
+
+                if incoming[to] == 0 {
+                    ready.push(to);
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// Sort one layer by the mean position of each node's neighbours in another,
+/// where `orient` maps an edge to a (node, neighbour) pair.  Nodes with no
+/// neighbours there keep their place.
+fn reorder(
+    layers: &mut [Vec<usize>],
+    layer: usize,
+    other: usize,
+    edges: &[(usize, usize)],
+    orient: impl Fn((usize, usize)) -> (usize, usize),
+) {
+    let position = |node: usize| layers[other].iter().position(|n| *n == node);
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut keyed: Vec<_> = layers[layer]
+        .iter()
+        .enumerate()
+        .map(|(i, &node)| {
+            let near: Vec<_> = edges
+                .iter()
+                .map(|&e| orient(e))
+                .filter(|(n, _)| *n == node)
+                .filter_map(|(_, m)| position(m))
+                .collect();
+
+            let key = if near.is_empty() {
+                i as f64
+            } else {
+                near.iter().sum::<usize>() as f64 / near.len() as f64
+            };
+
+            (key, node)
+        })
+        .collect();
+
+    keyed.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    layers[layer] = keyed.into_iter().map(|(_, n)| n).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_nodes() {
+        assert!(layers(0, &[]).is_empty());
+    }
+
+    #[test]
+    fn cycle() {
+        // The edge closing the loop is left out of the layering
+        assert_eq!(layers(3, &[(0, 1), (1, 2), (2, 0)]), [[0], [1], [2]]);
+        assert_eq!(acyclic(3, &[(0, 1), (1, 2), (2, 0)]), [(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn self_loop() {
+        assert_eq!(layers(2, &[(0, 0), (0, 1)]), [[0], [1]]);
+        assert_eq!(layers(1, &[(0, 0)]), [[0]]);
+    }
+
+    #[test]
+    fn stable_order() {
+        // Unconnected nodes and ties keep their original order
+        assert_eq!(layers(3, &[]), [[0, 1, 2]]);
+        assert_eq!(layers(3, &[(0, 1), (0, 2)]), [vec![0], vec![1, 2]]);
+
+        // Otherwise nodes are ordered to keep edges from crossing
+        assert_eq!(layers(4, &[(0, 3), (1, 2)]), [[0, 1], [3, 2]]);
+    }
+}
@@ -0,0 +1,298 @@
+use std::{fmt, fmt::Write};
+
+use num_traits::Zero;
+
+use super::{clusters, edge_label, ends, node_id, node_label};
+use crate::{
+    config::Config,
+    items::{Amount, ItemStacks, TimeUnit},
+    plan::{Node, Plan},
+    HashMap,
+};
+
+/// Quote a Mermaid label, joining lines with line breaks
+fn quote(lines: &[String]) -> String {
+    let lines: Vec<_> = lines.iter().map(|l| l.replace('"', "#quot;")).collect();
+
+    format!("\"{}\"", lines.join("<br/>"))
+}
+
+fn write_node(
+    f: &mut String,
+    indent: &str,
+    plan: &Plan,
+    config: &Config,
+    node: Node,
+) -> fmt::Result {
+    let label = quote(&node_label(plan, config, node));
+    let id = node_id(node);
+
+    match node {
+        Node::Raw(_) | Node::Available(_) => writeln!(f, "{}{}[/{}\\]", indent, id, label),
+        Node::Recipe(_) => writeln!(f, "{}{}[{}]", indent, id, label),
+        Node::Output(_) => writeln!(f, "{}{}([{}])", indent, id, label),
+        Node::Surplus(_) | Node::Sink(_) => writeln!(f, "{}{}[[{}]]", indent, id, label),
+    }
+}
+
+fn write_flowchart(f: &mut String, plan: &Plan, config: &Config, unit: TimeUnit) -> fmt::Result {
+    writeln!(f, "```mermaid")?;
+    writeln!(f, "flowchart LR")?;
+
+    for (machine, recipes) in clusters(plan) {
+        writeln!(f, "  subgraph cluster_{} [{}]", machine, quote(std::slice::from_ref(&machine)))?;
+
+        for i in recipes {
+            write_node(f, "    ", plan, config, Node::Recipe(i))?;
+        }
+
+        writeln!(f, "  end")?;
+    }
+
+    for node in ends(plan) {
+        write_node(f, "  ", plan, config, node)?;
+    }
+
+    let mut over = vec![];
+
+    for (i, edge) in plan.edges().iter().enumerate() {
+        writeln!(
+            f,
+            "  {} -->|{}| {}",
+            node_id(edge.from()),
+            quote(&edge_label(config, edge, unit)),
+            node_id(edge.to())
+        )?;
+
+        if edge.lines().is_over() {
+            over.push(i.to_string());
+        }
+    }
+
+    if !over.is_empty() {
+        writeln!(f, "  linkStyle {} stroke:red,color:red", over.join(","))?;
+    }
+
+    writeln!(f, "```")
+}
+
+fn write_table(f: &mut String, head: &[&str], rows: &[Vec<String>]) -> fmt::Result {
+    let row = |cells: &mut dyn Iterator<Item = String>| -> String {
+        let cells: Vec<_> = cells.map(|c| c.replace('|', "\\|")).collect();
+
+        format!("| {} |", cells.join(" | "))
+    };
+
+    writeln!(f, "{}", row(&mut head.iter().map(|h| (*h).to_owned())))?;
+    writeln!(f, "{}", row(&mut head.iter().map(|_| "---".to_owned())))?;
+
+    for cells in rows {
+        writeln!(f, "{}", row(&mut cells.iter().cloned()))?;
+    }
+
+    Ok(())
+}
+
+/// Write a titled table of item rates sorted by item name, or nothing if
+/// there are none
+fn write_rates(
+    f: &mut String,
+    config: &Config,
+    title: &str,
+    stacks: &ItemStacks,
+    unit: TimeUnit,
+) -> fmt::Result {
+    if stacks.is_empty() {
+        return Ok(());
+    }
+
+    let mut rows: Vec<_> = stacks
+        .iter()
+        .map(|(item, amt)| {
+            vec![
+                config.item_name(*item).into_owned(),
+                format!(
+                    "{}{}/{}",
+                    unit.convert(amt),
+                    config.item_form(*item).unit(),
+                    unit
+                ),
+            ]
+        })
+        .collect();
+    rows.sort();
+
+    writeln!(f)?;
+    writeln!(f, "## {}", title)?;
+    writeln!(f)?;
+    write_table(f, &["Item", "Rate"], &rows)
+}
+
+fn write_machines(f: &mut String, plan: &Plan) -> fmt::Result {
+    let mut built: HashMap<_, Amount> = HashMap::default();
+
+    for rate in plan.recipes() {
+        *built.entry(rate.recipe().machine()).or_insert_with(Amount::zero) += rate.build().0;
+    }
+
+    let mut rows: Vec<_> = plan
+        .machines()
+        .iter()
+        .map(|(machine, count)| {
+            vec![
+                machine.to_string(),
+                count.to_string(),
+                built.get(machine).map_or_else(String::new, ToString::to_string),
+            ]
+        })
+        .collect();
+    rows.sort();
+
+    writeln!(f)?;
+    writeln!(f, "## Machines")?;
+    writeln!(f)?;
+    write_table(f, &["Machine", "Running", "Built"], &rows)
+}
+
+fn write_raw(f: &mut String, plan: &Plan, config: &Config, unit: TimeUnit) -> fmt::Result {
+    if plan.inputs().is_empty() {
+        return Ok(());
+    }
+
+    let mut rows: Vec<_> = plan
+        .inputs()
+        .iter()
+        .map(|(item, amt)| {
+            let nodes = plan
+                .extraction()
+                .iter()
+                .find(|(i, _)| i == item)
+                .map_or_else(String::new, |(_, e)| e.to_string());
+
+            vec![
+                config.item_name(*item).into_owned(),
+                format!(
+                    "{}{}/{}",
+                    unit.convert(amt),
+                    config.item_form(*item).unit(),
+                    unit
+                ),
+                nodes,
+            ]
+        })
+        .collect();
+    rows.sort();
+
+    writeln!(f)?;
+    writeln!(f, "## Raw inputs")?;
+    writeln!(f)?;
+    write_table(f, &["Item", "Rate", "Nodes"], &rows)
+}
+
+fn write_power(f: &mut String, plan: &Plan) -> fmt::Result {
+    let mut rows: Vec<_> = plan
+        .power()
+        .iter()
+        .map(|(machine, mw)| vec![machine.to_string(), mw.to_string()])
+        .collect();
+    rows.sort();
+    rows.push(vec!["**Total**".into(), format!("**{}**", plan.total_power())]);
+
+    writeln!(f)?;
+    writeln!(f, "## Power")?;
+    writeln!(f)?;
+    write_table(f, &["Machine", "MW"], &rows)
+}
+
+fn write_alternates(f: &mut String, plan: &Plan) -> fmt::Result {
+    writeln!(f)?;
+    writeln!(f, "## Alternate recipes")?;
+    writeln!(f)?;
+
+    if plan.alternates().is_empty() {
+        return writeln!(f, "No alternate recipes are enabled.");
+    }
+
+    plan.alternates().iter().try_for_each(|r| writeln!(f, "- {}", r))
+}
+
+/// Render a plan as a Markdown report, sorted throughout so that reports of
+/// the same plan are identical.  Recipes and edges keep the plan's own order,
+/// which is the same every time the same request is calculated.
+pub fn render(plan: &Plan, config: &Config, unit: TimeUnit) -> Result<String, fmt::Error> {
+    let mut f = String::new();
+
+    writeln!(f, "# Production plan")?;
+    writeln!(f)?;
+    write_flowchart(&mut f, plan, config, unit)?;
+    write_machines(&mut f, plan)?;
+    write_raw(&mut f, plan, config, unit)?;
+    write_rates(&mut f, config, "Available inputs used", plan.used(), unit)?;
+    write_rates(&mut f, config, "Outputs", plan.outputs(), unit)?;
+    write_rates(&mut f, config, "Surplus", plan.surplus(), unit)?;
+    write_rates(&mut f, config, "Sunk", plan.sunk(), unit)?;
+    write_power(&mut f, plan)?;
+    write_alternates(&mut f, plan)?;
+
+    Ok(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::tests::load,
+        items::Item,
+        plan::{Objective, Settings},
+    };
+
+    const CONFIG: &str = "
+raw: [crude_oil, iron_ore, copper_ore]
+items:
+  crude_oil: { form: liquid }
+  heavy_oil_residue: { form: liquid }
+machines:
+  constructor: { power: 4 }
+  refinery: { power: 30 }
+  smelter: { power: 4 }
+recipes:
+  constructor:
+    - { make: 2 iron_plate, from: 3 iron_ingot, in: 6 }
+    - { make: 1 wire, from: 1 iron_ingot, in: 4, name: Iron Wire, alternate: true }
+    - { make: 1 wire, from: 1 copper_ingot, in: 4, name: Wire }
+  refinery:
+    - { make: 2 plastic 1m3 heavy_oil_residue, from: 3m3 crude_oil, in: 6 }
+  smelter:
+    - { make: 1 iron_ingot, from: 1 iron_ore, in: 2 }
+    - { make: 1 copper_ingot, from: 1 copper_ore, in: 2 }
+";
+
+    fn report() -> String {
+        let config = load(CONFIG).unwrap();
+        let want = ItemStacks::new(
+            [("iron_plate", "20"), ("wire", "30"), ("plastic", "10")]
+                .into_iter()
+                .map(|(i, a)| (Item::new(i).unwrap(), a.parse().unwrap()))
+                .collect(),
+        );
+        let mut settings = Settings::default();
+        settings.set_enabled("iron_wire".parse().unwrap(), true);
+
+        let plan =
+            Plan::calculate(&config, &want, &ItemStacks::empty(), &settings, Objective::Raw)
+                .unwrap();
+
+        render(&plan, &config, TimeUnit::Minute).unwrap()
+    }
+
+    #[test]
+    fn stable() {
+        let first = report();
+
+        for _ in 0..4 {
+            assert_eq!(report(), first);
+        }
+
+        assert!(first.contains("## Alternate recipes\n\n- Iron Wire\n"), "{}", first);
+    }
+}
@@ -0,0 +1,208 @@
+mod dot;
+mod html;
+mod layout;
+mod markdown;
+
+use std::{collections::BTreeMap, fmt, fs, path::Path, str::FromStr};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    config::Config,
+    items::TimeUnit,
+    plan::{Edge, Node, Plan},
+    prelude::*,
+};
+
+#[derive(Debug, Error)]
+#[error("Unrecognized export format {0:?}")]
+pub struct InvalidFormat(String);
+
+/// A file format a plan can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// A Graphviz graph
+    Dot,
+    /// A Markdown report with a Mermaid flowchart
+    Markdown,
+    /// A self-contained HTML report with an SVG graph
+    Html,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dot => "dot",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = InvalidFormat;
+
+    fn from_str(s: &str) -> Result<Self, InvalidFormat> {
+        Ok(match s {
+            "dot" | "graphviz" => Self::Dot,
+            "md" | "markdown" | "mermaid" => Self::Markdown,
+            "html" | "svg" => Self::Html,
+            s => return Err(InvalidFormat(s.to_owned())),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Unrecognized output format {0:?}")]
+pub struct InvalidOutput(String);
+
+/// How plans and state are printed to standard output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Output {
+    /// Human-readable text
+    Text,
+    Json,
+    Yaml,
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        })
+    }
+}
+
+impl FromStr for Output {
+    type Err = InvalidOutput;
+
+    fn from_str(s: &str) -> Result<Self, InvalidOutput> {
+        Ok(match s {
+            "text" => Self::Text,
+            "json" => Self::Json,
+            "yaml" | "yml" => Self::Yaml,
+            s => return Err(InvalidOutput(s.to_owned())),
+        })
+    }
+}
+
+impl Output {
+    /// Serialize a value for printing, or return `None` for text, which the
+    /// caller formats itself
+    pub fn serialize(self, value: &impl Serialize) -> Result<Option<String>> {
+        Ok(match self {
+            Self::Text => None,
+            Self::Json => {
+                Some(serde_json::to_string_pretty(value).context("Failed to write JSON")?)
+            },
+            Self::Yaml => Some(serde_yaml::to_string(value).context("Failed to write YAML")?),
+        })
+    }
+}
+
+/// Write a plan to a file, with rates given per `unit`
+pub fn write(
+    path: impl AsRef<Path>,
+    format: Format,
+    plan: &Plan,
+    config: &Config,
+    unit: TimeUnit,
+) -> Result<()> {
+    let out = match format {
+        Format::Dot => dot::render(plan, config, unit),
+        Format::Markdown => markdown::render(plan, config, unit),
+        Format::Html => html::render(plan, config, unit),
+    }
+    .context("Failed to format plan")?;
+
+    fs::write(path, out).with_context(|| anyhow!("Failed to write {} file", format))
+}
+
+/// The indices of a plan's recipes, grouped by the machine they run in
+fn clusters(plan: &Plan) -> BTreeMap<String, Vec<usize>> {
+    let mut clusters: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+    for (i, rate) in plan.recipes().iter().enumerate() {
+        clusters
+            .entry(rate.recipe().machine().to_string())
+            .or_default()
+            .push(i);
+    }
+
+    clusters
+}
+
+/// Every node of a plan's graph other than its recipes, in the order they
+/// first appear in its edges
+fn ends(plan: &Plan) -> Vec<Node> {
+    let mut ends = vec![];
+
+    for edge in plan.edges() {
+        for node in [edge.from(), edge.to()] {
+            if !matches!(node, Node::Recipe(_)) && !ends.contains(&node) {
+                ends.push(node);
+            }
+        }
+    }
+
+    ends
+}
+
+/// A unique identifier for a node of a plan's graph
+fn node_id(node: Node) -> String {
+    match node {
+        Node::Raw(item) => format!("raw_{}", item),
+        Node::Available(item) => format!("have_{}", item),
+        Node::Recipe(i) => format!("recipe_{}", i),
+        Node::Output(item) => format!("out_{}", item),
+        Node::Surplus(item) => format!("surplus_{}", item),
+        Node::Sink(item) => format!("sink_{}", item),
+    }
+}
+
+/// The lines of text describing a node of a plan's graph
+fn node_label(plan: &Plan, config: &Config, node: Node) -> Vec<String> {
+    match node {
+        Node::Raw(item) => {
+            let mut lines = vec![config.item_name(item).into_owned()];
+
+            lines.extend(
+                plan.extraction()
+                    .iter()
+                    .filter(|(i, _)| *i == item)
+                    .map(|(_, e)| e.to_string()),
+            );
+
+            lines
+        },
+        Node::Available(item) => vec![format!("Available {}", config.item_name(item))],
+        Node::Recipe(i) => {
+            let rate = &plan.recipes()[i];
+
+            vec![
+                config.describe(rate.recipe()),
+                format!("{} {}", rate.recipe().machine(), rate),
+            ]
+        },
+        Node::Output(item) => vec![format!("Output {}", config.item_name(item))],
+        Node::Surplus(item) => vec![format!("Surplus {}", config.item_name(item))],
+        Node::Sink(item) => vec![format!("Sink {}", config.item_name(item))],
+    }
+}
+
+/// The lines of text describing an edge of a plan's graph
+fn edge_label(config: &Config, edge: &Edge, unit: TimeUnit) -> Vec<String> {
+    vec![
+        config.item_name(edge.item()).into_owned(),
+        format!(
+            "{}{}/{}",
+            unit.convert(edge.rate()),
+            config.item_form(edge.item()).unit(),
+            unit
+        ),
+        edge.lines().to_string(),
+    ]
+}
@@ -0,0 +1,472 @@
+use std::{collections::BTreeMap, fs, io::Write, iter, path::Path};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    config::{format, Config, Form},
+    items::{litres_to_m3, recipe_key, Amount, Rate},
+    prelude::*,
+    HashMap, HashSet,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Group {
+    native_class: String,
+    classes: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct ItemClass {
+    #[serde(rename = "ClassName")]
+    class: String,
+    #[serde(rename = "mDisplayName")]
+    name: String,
+    #[serde(rename = "mForm", default)]
+    form: String,
+    #[serde(rename = "mStackSize", default)]
+    stack_size: String,
+    #[serde(rename = "mResourceSinkPoints", default)]
+    sink_points: String,
+}
+
+#[derive(Deserialize)]
+struct MachineClass {
+    #[serde(rename = "ClassName")]
+    class: String,
+    #[serde(rename = "mDisplayName")]
+    name: String,
+    #[serde(rename = "mPowerConsumption")]
+    power: String,
+}
+
+#[derive(Deserialize)]
+struct RecipeClass {
+    #[serde(rename = "ClassName")]
+    class: String,
+    #[serde(rename = "mDisplayName")]
+    name: String,
+    #[serde(rename = "mIngredients")]
+    ingredients: String,
+    #[serde(rename = "mProduct")]
+    products: String,
+    #[serde(rename = "mManufactoringDuration")]
+    duration: String,
+    #[serde(rename = "mProducedIn")]
+    produced_in: String,
+}
+
+struct ImportedItem {
+    key: String,
+    name: String,
+    form: Form,
+    stack_size: Option<u32>,
+    sink_points: Option<u64>,
+    raw: bool,
+}
+
+/// Read the game's `Docs.json` and write the equivalent config to `out`, or
+/// to standard output if no file is given
+pub fn run(docs: impl AsRef<Path>, out: Option<impl AsRef<Path>>) -> Result<()> {
+    let bytes = fs::read(docs).context("Failed to read Docs.json")?;
+    let config = convert(&decode(&bytes)?)?;
+    let yaml = serde_yaml::to_string(&config).context("Failed to serialize config")?;
+
+    // Make sure the output loads before writing it anywhere
+    Config::try_from(serde_yaml::from_str::<format::Config>(&yaml)?)
+        .context("Imported config is invalid")?;
+
+    match out {
+        Some(path) => fs::write(path, yaml).context("Failed to write config file")?,
+        None => std::io::stdout()
+            .write_all(yaml.as_bytes())
+            .context("Failed to write config")?,
+    }
+
+    Ok(())
+}
+
+/// Decode text using its byte order mark, as `Docs.json` is usually UTF-16
+fn decode(bytes: &[u8]) -> Result<String> {
+    let utf16 = |rest: &[u8], read: fn([u8; 2]) -> u16| {
+        let chunks = rest.chunks_exact(2);
+
+        if !chunks.remainder().is_empty() {
+            bail!("Truncated UTF-16 input");
+        }
+
+        let units: Vec<_> = chunks.map(|c| read([c[0], c[1]])).collect();
+
+        String::from_utf16(&units).context("Invalid UTF-16 input")
+    };
+
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8(rest.to_vec()).map_err(Into::into),
+        _ => String::from_utf8(bytes.to_vec()).context("Input has no byte order mark"),
+    }
+}
+
+/// Strip the path from a native class name such as
+/// `"Class'/Script/FactoryGame.FGRecipe'"`
+fn native_class(s: &str) -> &str {
+    s.trim_end_matches('\'')
+        .rsplit('.')
+        .next()
+        .unwrap_or(s)
+}
+
+/// Split a list of quoted class paths, such as a recipe's `mProducedIn`, into
+/// bare class names
+fn class_names(s: &str) -> impl Iterator<Item = &str> {
+    s.trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|path| native_class(path.trim().trim_matches('"')))
+        .filter(|class| !class.is_empty())
+}
+
+/// Parse an item amount, such as `"6.000000"`, scaling fluid amounts from
+/// litres to cubic metres
+fn amount(s: &str, form: Form) -> Result<Amount> {
+    let amt: Amount = s
+        .trim()
+        .parse()
+        .with_context(|| anyhow!("Invalid amount {:?}", s))?;
+
+    Ok(match form {
+        Form::Solid => amt,
+        Form::Liquid | Form::Gas => litres_to_m3(amt),
+    })
+}
+
+/// Look up the number of items in one stack, which fluids don't have
+fn stack_size(s: &str, form: Form) -> Option<u32> {
+    if form != Form::Solid {
+        return None;
+    }
+
+    match s {
+        "SS_ONE" => Some(1),
+        "SS_SMALL" => Some(50),
+        "SS_MEDIUM" => Some(100),
+        "SS_BIG" => Some(200),
+        "SS_HUGE" => Some(500),
+        _ => None,
+    }
+}
+
+/// Parse a list of item references, marking fluid amounts as volumes, or
+/// return `None` if any item is unknown
+fn items(s: &str, known: &HashMap<String, ImportedItem>) -> Option<Result<format::Items>> {
+    lazy_static! {
+        static ref REF_REGEX: Regex =
+            Regex::new(r#"\.([A-Za-z0-9_]+)["']*,\s*Amount=([\d.]+)"#).unwrap();
+    }
+
+    REF_REGEX
+        .captures_iter(s)
+        .map(|caps| {
+            let item = known.get(&caps[1])?;
+
+            Some(amount(&caps[2], item.form).map(|a| {
+                let rate = match item.form {
+                    Form::Solid => Rate::from(a),
+                    Form::Liquid | Form::Gas => Rate::from(a).in_m3(),
+                };
+
+                format::Item(rate, item.key.clone())
+            }))
+        })
+        .collect::<Option<Result<_>>>()
+        .map(|r| r.map(format::Items))
+}
+
+/// Assign each class a key derived from its display name, falling back to its
+/// class name if two display names collide
+fn assign_keys<'a>(
+    classes: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> HashMap<&'a str, String> {
+    let mut used = HashSet::default();
+    let mut ret = HashMap::default();
+
+    for (class, name) in classes {
+        let mut key = recipe_key(name);
+
+        if key.is_empty() || !used.insert(key.clone()) {
+            key = recipe_key(class);
+            used.insert(key.clone());
+        }
+
+        ret.insert(class, key);
+    }
+
+    ret
+}
+
+/// Convert the recipes made in one of `machines`, keyed by class name, naming
+/// each uniquely
+fn convert_recipes(
+    recipe_classes: Vec<RecipeClass>,
+    machines: &HashMap<&str, String>,
+    known: &HashMap<String, ImportedItem>,
+) -> Result<BTreeMap<String, Vec<format::Recipe>>> {
+    let mut recipes: BTreeMap<String, Vec<format::Recipe>> = BTreeMap::new();
+    let mut names = HashSet::default();
+
+    for recipe in recipe_classes {
+        let machine = match class_names(&recipe.produced_in).find_map(|c| machines.get(c)) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        let (make, from) = if let (Some(make), Some(from)) = (
+            items(&recipe.products, known),
+            items(&recipe.ingredients, known),
+        ) {
+            (make?, from?)
+        } else {
+            warn!("Skipping recipe {} with unknown items", recipe.class);
+            continue;
+        };
+
+        // Tell apart recipes with the same name by their machine, and then by
+        // number if that isn't enough.  There are more candidates than names
+        // taken, so one is always free.
+        let name = iter::once(recipe.name.clone())
+            .chain(iter::once(format!("{} ({})", recipe.name, machine)))
+            .chain((2..=names.len() + 1).map(|n| format!("{} ({} {})", recipe.name, machine, n)))
+            .find(|n| names.insert(recipe_key(n)))
+            .expect("Every name was taken");
+
+        recipes.entry(machine.clone()).or_default().push(format::Recipe {
+            make,
+            from,
+            in_sec: Some(amount(&recipe.duration, Form::Solid)?),
+            name: Some(name),
+            alternate: recipe.class.contains("Alternate"),
+        });
+    }
+
+    Ok(recipes)
+}
+
+/// Convert the contents of `Docs.json` into a config, keeping only recipes
+/// made in manufacturing buildings
+fn convert(json: &str) -> Result<format::Config> {
+    let groups: Vec<Group> = serde_json::from_str(json).context("Failed to parse Docs.json")?;
+
+    let mut item_classes = vec![];
+    let mut machine_classes = vec![];
+    let mut recipe_classes = vec![];
+
+    for group in groups {
+        let native = native_class(&group.native_class);
+        let is_item = native.contains("Descriptor")
+            && !matches!(native, "FGBuildingDescriptor" | "FGVehicleDescriptor");
+
+        for class in group.classes {
+            if is_item {
+                let raw = native == "FGResourceDescriptor";
+                item_classes.push((raw, serde_json::from_value(class)?));
+            } else if native.starts_with("FGBuildableManufacturer") {
+                machine_classes.push(serde_json::from_value::<MachineClass>(class)?);
+            } else if native == "FGRecipe" {
+                recipe_classes.push(serde_json::from_value::<RecipeClass>(class)?);
+            }
+        }
+    }
+
+    item_classes.sort_by(|(_, a): &(bool, ItemClass), (_, b)| a.class.cmp(&b.class));
+    machine_classes.sort_by(|a, b| a.class.cmp(&b.class));
+    recipe_classes.sort_by(|a, b| a.class.cmp(&b.class));
+
+    let item_keys = assign_keys(item_classes.iter().map(|(_, i)| (&*i.class, &*i.name)));
+    let machine_keys = assign_keys(machine_classes.iter().map(|m| (&*m.class, &*m.name)));
+
+    let known: HashMap<_, _> = item_classes
+        .iter()
+        .map(|(raw, i)| {
+            let form = match i.form.as_str() {
+                "RF_LIQUID" => Form::Liquid,
+                "RF_GAS" => Form::Gas,
+                _ => Form::Solid,
+            };
+
+            (i.class.clone(), ImportedItem {
+                key: item_keys[&*i.class].clone(),
+                name: i.name.clone(),
+                form,
+                stack_size: stack_size(&i.stack_size, form),
+                sink_points: i.sink_points.parse().ok().filter(|p| *p > 0),
+                raw: *raw,
+            })
+        })
+        .collect();
+
+    let machines: BTreeMap<_, _> = machine_classes
+        .iter()
+        .map(|m| {
+            Ok((machine_keys[&*m.class].clone(), format::Machine {
+                power: amount(&m.power, Form::Solid)?,
+                footprint: None,
+                cost: None,
+                inputs: None,
+                outputs: None,
+            }))
+        })
+        .collect::<Result<_>>()?;
+
+    let recipes = convert_recipes(recipe_classes, &machine_keys, &known)?;
+
+    // Only keep items something can be made from or into
+    let used: HashSet<_> = recipes
+        .values()
+        .flatten()
+        .flat_map(|r| r.make.0.iter().chain(&r.from.0))
+        .map(|format::Item(_, key)| key.clone())
+        .collect();
+
+    let mut known: Vec<_> = known.into_values().filter(|i| used.contains(&i.key)).collect();
+    known.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(format::Config {
+        raw: known
+            .iter()
+            .filter(|i| i.raw)
+            .map(|i| format::Raw::Unlimited(i.key.clone()))
+            .collect(),
+        items: known
+            .into_iter()
+            .map(|i| {
+                (i.key, format::ItemInfo {
+                    name: Some(i.name),
+                    form: i.form,
+                    stack_size: i.stack_size,
+                    sink_points: i.sink_points,
+                    category: None,
+                })
+            })
+            .collect(),
+        machines,
+        recipes,
+        logistics: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(name: &str, produced_in: &str) -> RecipeClass {
+        let item = |class: &str, amount: u32| {
+            format!(
+                "((ItemClass=BlueprintGeneratedClass'\"/Game/{0}.{0}\"',Amount={1}))",
+                class, amount
+            )
+        };
+
+        RecipeClass {
+            class: format!("Recipe_{}_C", recipe_key(name)),
+            name: name.into(),
+            ingredients: item("Desc_IronIngot_C", 3),
+            products: item("Desc_IronPlate_C", 2),
+            duration: "6.000000".into(),
+            produced_in: produced_in.into(),
+        }
+    }
+
+    fn known() -> HashMap<String, ImportedItem> {
+        [("Desc_IronIngot_C", "iron_ingot"), ("Desc_IronPlate_C", "iron_plate")]
+            .into_iter()
+            .map(|(class, key)| {
+                (class.to_owned(), ImportedItem {
+                    key: key.into(),
+                    name: key.into(),
+                    form: Form::Solid,
+                    stack_size: None,
+                    sink_points: None,
+                    raw: false,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fixture() {
+        let docs = decode(include_bytes!("../fixtures/Docs.json")).unwrap();
+        let yaml = serde_yaml::to_string(&convert(&docs).unwrap()).unwrap();
+
+        assert_eq!(yaml, include_str!("../fixtures/docs.yml"));
+    }
+
+    #[test]
+    fn produced_in_exact_class() {
+        let machines: HashMap<_, _> = [
+            ("Build_Constructor_C", "constructor".to_owned()),
+            ("Build_Constructor_C_Mk2", "constructor_mk2".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+
+        let recipes = convert_recipes(
+            vec![
+                recipe(
+                    "Iron Plate",
+                    "(\"/Game/Build_Constructor_C_Mk2.Build_Constructor_C_Mk2\",\
+                     \"/Game/BP_WorkBenchComponent.BP_WorkBenchComponent_C\")",
+                ),
+                recipe("Iron Sheet", "(\"/Game/Build_Constructor.Build_Constructor_C\")"),
+                recipe("Hand Plate", "(\"/Game/BP_WorkBench.BP_WorkBenchComponent_C\")"),
+            ],
+            &machines,
+            &known(),
+        )
+        .unwrap();
+
+        let names: Vec<_> = recipes
+            .iter()
+            .flat_map(|(m, rs)| rs.iter().map(move |r| (&**m, r.name.as_deref().unwrap())))
+            .collect();
+
+        assert_eq!(names, [
+            ("constructor", "Iron Sheet"),
+            ("constructor_mk2", "Iron Plate"),
+        ]);
+    }
+
+    #[test]
+    fn unique_names() {
+        let machines: HashMap<_, _> = [("Build_Constructor_C", "constructor".to_owned())]
+            .into_iter()
+            .collect();
+        let produced_in = "(\"/Game/Build_Constructor.Build_Constructor_C\")";
+
+        let recipes = convert_recipes(
+            vec![
+                recipe("Iron Plate", produced_in),
+                recipe("Iron Plate", produced_in),
+                recipe("Iron Plate (constructor)", produced_in),
+                recipe("Iron Plate", produced_in),
+            ],
+            &machines,
+            &known(),
+        )
+        .unwrap();
+
+        let names: Vec<_> = recipes["constructor"]
+            .iter()
+            .map(|r| r.name.as_deref().unwrap())
+            .collect();
+
+        assert_eq!(names, [
+            "Iron Plate",
+            "Iron Plate (constructor)",
+            "Iron Plate (constructor) (constructor)",
+            "Iron Plate (constructor 2)",
+        ]);
+    }
+}
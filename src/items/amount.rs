@@ -1,55 +1,101 @@
 use std::{
-    ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
+    fmt,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
     str::FromStr,
 };
 
+use num_bigint::BigInt;
 use num_derive::{One, Zero};
-use ordered_float::NotNan;
-use serde::de;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+use serde::{de, ser};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
-#[error("Invalid item amount {0:?}")]
-pub struct AmountError(f64);
+#[error("Invalid item amount {0}")]
+pub struct AmountError(BigRational);
 
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("{0}")]
     Amount(#[from] AmountError),
-    #[error("{0}")]
-    Std(#[from] std::num::ParseFloatError),
+    #[error("Invalid number {0:?}")]
+    Syntax(String),
+    #[error("Division by zero in {0:?}")]
+    DivideByZero(String),
+    #[error("Invalid item amount {0}")]
+    NotFinite(f64),
 }
 
-// TODO: subject to change.  i'd like to not use IEEE754 values
+/// A non-negative quantity, stored as an exact fraction so production ratios
+/// never accumulate rounding error
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Zero, One)]
-pub struct Amount(NotNan<f64>);
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Zero, One)]
+pub struct Amount(BigRational);
 
 impl Amount {
-    pub fn new(f: f64) -> Result<Self, AmountError> {
-        Self::new_not_nan(NotNan::new(f).map_err(|_| AmountError(f))?)
+    pub fn new(r: BigRational) -> Result<Self, AmountError> {
+        if r.is_negative() {
+            Err(AmountError(r))
+        } else {
+            Ok(Self(r))
+        }
+    }
+
+    pub fn from_integer(i: u64) -> Self { Self(BigRational::from_integer(i.into())) }
+
+    pub fn checked_sub(&self, rhs: &Amount) -> Option<Self> { Self::new(&self.0 - &rhs.0).ok() }
+
+    #[inline]
+    pub fn as_ratio(&self) -> &BigRational { &self.0 }
+
+    #[inline]
+    pub fn into_ratio(self) -> BigRational { self.0 }
+
+    /// Round a float to the given number of decimal places, for quantities that
+    /// can't be computed exactly
+    pub fn from_f64_approx(f: f64, places: u16) -> Result<Self, ParseError> {
+        let scale = 10_f64.powi(places.into());
+        let num = BigInt::from_f64((f * scale).round()).ok_or(ParseError::NotFinite(f))?;
+
+        Self::new(BigRational::new(num, num_traits::pow(BigInt::from(10), places.into())))
+            .map_err(Into::into)
     }
 
-    pub fn new_not_nan(f: NotNan<f64>) -> Result<Self, AmountError> {
-        if f == -0.0 {
-            Ok(Self(-f))
-        } else if f.is_sign_negative() {
-            Err(AmountError(*f))
+    pub fn floor(&self) -> Self { Self(self.0.floor()) }
+
+    pub fn ceil(&self) -> Self { Self(self.0.ceil()) }
+
+    /// Format this amount without rounding, as a decimal if it has a finite
+    /// decimal expansion or else as a fraction such as `"1/3"`
+    pub fn to_exact_string(&self) -> String {
+        let mut den = self.0.denom().clone();
+        let mut places = [0_usize; 2];
+
+        for (factor, count) in [2_u32, 5].into_iter().zip(&mut places) {
+            let factor = BigInt::from(factor);
+
+            while (&den % &factor).is_zero() {
+                den /= &factor;
+                *count += 1;
+            }
+        }
+
+        if den.is_one() {
+            format!("{:.*}", places[0].max(places[1]), self)
         } else {
-            Ok(Self(f))
+            format!("{}/{}", self.0.numer(), self.0.denom())
         }
     }
 
-    pub fn checked_sub(self, rhs: Amount) -> Option<Self> { Self::new_not_nan(self.0 - rhs.0).ok() }
+    /// Approximate this amount as a float, for display or heuristics only
+    pub fn to_f64(&self) -> f64 { self.0.to_f64().unwrap_or(f64::INFINITY) }
 
     #[inline]
-    fn mutate<T>(&mut self, f: impl FnOnce(&mut NotNan<f64>) -> T) -> T {
+    fn mutate<T>(&mut self, f: impl FnOnce(&mut BigRational) -> T) -> T {
         let ret = f(&mut self.0);
 
-        debug_assert!(
-            !self.0.is_sign_negative(),
-            "Amount produced with invalid value!"
-        );
+        debug_assert!(!self.0.is_negative(), "Amount produced with invalid value!");
 
         ret
     }
@@ -60,80 +106,215 @@ impl Amount {
     }
 }
 
+fn parse_decimal(s: &str) -> Option<BigRational> {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+
+    if (int.is_empty() && frac.is_empty())
+        || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let digits: BigInt = format!("{}{}", int, frac).parse().ok()?;
+
+    Some(BigRational::new(
+        digits,
+        num_traits::pow(BigInt::from(10), frac.len()),
+    ))
+}
+
+impl fmt::Display for Amount {
+    /// Formats as a decimal, rounded to the requested precision or to at most
+    /// four places with trailing zeros removed
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let places = f.precision().unwrap_or(4);
+        let scale = num_traits::pow(BigInt::from(10), places);
+        let scaled = (&self.0 * BigRational::from_integer(scale.clone())).round().to_integer();
+        let int = &scaled / &scale;
+        let frac = (&scaled % &scale).to_string();
+
+        let mut s = int.to_string();
+
+        if places > 0 {
+            let frac = format!("{:0>width$}", frac, width = places);
+            let frac = if f.precision().is_some() {
+                &frac
+            } else {
+                frac.trim_end_matches('0')
+            };
+
+            if !frac.is_empty() {
+                s.push('.');
+                s.push_str(frac);
+            }
+        }
+
+        // pad() would treat the precision as a maximum width, so pad the
+        // digits as a number instead
+        f.pad_integral(true, "", &s)
+    }
+}
+
 impl FromStr for Amount {
     type Err = ParseError;
 
-    fn from_str(s: &str) -> Result<Self, ParseError> { Self::new(s.parse()?).map_err(Into::into) }
+    /// Parses a decimal such as `"7.5"` or a fraction such as `"1/3"`
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let syntax = || ParseError::Syntax(s.to_owned());
+        let trimmed = s.trim();
+
+        let value = if let Some((num, den)) = trimmed.split_once('/') {
+            let num = parse_decimal(num.trim()).ok_or_else(syntax)?;
+            let den = parse_decimal(den.trim()).ok_or_else(syntax)?;
+
+            if den.is_zero() {
+                return Err(ParseError::DivideByZero(s.to_owned()));
+            }
+
+            num / den
+        } else {
+            parse_decimal(trimmed).ok_or_else(syntax)?
+        };
+
+        Self::new(value).map_err(Into::into)
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(i: u64) -> Self { Self::from_integer(i) }
 }
 
-impl TryFrom<f64> for Amount {
+impl TryFrom<BigRational> for Amount {
     type Error = AmountError;
 
-    fn try_from(f: f64) -> Result<Self, AmountError> { Self::new(f) }
+    fn try_from(r: BigRational) -> Result<Self, AmountError> { Self::new(r) }
+}
+
+impl From<Amount> for BigRational {
+    fn from(a: Amount) -> Self { a.0 }
 }
 
 impl<'de> de::Deserialize<'de> for Amount {
     fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
-        f64::deserialize(de).and_then(|f| Self::new(f).map_err(<D::Error as de::Error>::custom))
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(r#"a non-negative number or fraction, such as 7.5 or "1/3""#)
+            }
+
+            fn visit_u64<E: de::Error>(self, u: u64) -> Result<Amount, E> {
+                Ok(Amount::from_integer(u))
+            }
+
+            fn visit_i64<E: de::Error>(self, i: i64) -> Result<Amount, E> {
+                Amount::new(BigRational::from_integer(i.into())).map_err(E::custom)
+            }
+
+            // Round-trip through the shortest decimal representation so that
+            // e.g. 0.1 is read as exactly one tenth
+            fn visit_f64<E: de::Error>(self, f: f64) -> Result<Amount, E> {
+                if !f.is_finite() {
+                    return Err(E::custom(ParseError::NotFinite(f)));
+                }
+
+                format!("{}", f).parse().map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Amount, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        de.deserialize_any(Visitor)
+    }
+}
+
+impl ser::Serialize for Amount {
+    /// Serializes as an integer or short decimal where possible, and otherwise
+    /// as an exact string
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        if let Some(u) = self.0.is_integer().then(|| self.0.to_integer().to_u64()).flatten() {
+            return ser.serialize_u64(u);
+        }
+
+        let s = self.to_exact_string();
+
+        match s.parse::<f64>() {
+            Ok(f) if s.len() <= 16 && format!("{}", f) == s => ser.serialize_f64(f),
+            _ => ser.serialize_str(&s),
+        }
     }
 }
 
-impl AddAssign<Amount> for Amount {
-    fn add_assign(&mut self, rhs: Amount) { self.mutate(|f| *f += rhs.0) }
+impl AddAssign<&Amount> for Amount {
+    fn add_assign(&mut self, rhs: &Amount) { self.mutate(|r| *r += &rhs.0) }
+}
+
+impl SubAssign<&Amount> for Amount {
+    fn sub_assign(&mut self, rhs: &Amount) { *self = self.checked_sub(rhs).unwrap(); }
 }
 
-impl SubAssign<Amount> for Amount {
-    fn sub_assign(&mut self, rhs: Amount) { *self = self.checked_sub(rhs).unwrap(); }
+impl MulAssign<&Amount> for Amount {
+    fn mul_assign(&mut self, rhs: &Amount) { self.mutate(|r| *r *= &rhs.0) }
 }
 
-impl MulAssign<Amount> for Amount {
-    fn mul_assign(&mut self, rhs: Amount) { self.mutate(|f| *f *= rhs.0) }
+impl DivAssign<&Amount> for Amount {
+    fn div_assign(&mut self, rhs: &Amount) { self.mutate(|r| *r /= &rhs.0) }
 }
 
-impl Add<Amount> for Amount {
+impl Add<&Amount> for Amount {
     type Output = Self;
 
-    fn add(self, rhs: Self) -> Self { self.with(|s| s.add_assign(rhs)) }
+    fn add(self, rhs: &Amount) -> Self { self.with(|s| s.add_assign(rhs)) }
 }
 
-impl Sub<Amount> for Amount {
+impl Sub<&Amount> for Amount {
     type Output = Self;
 
-    fn sub(self, rhs: Self) -> Self { self.with(|s| s.sub_assign(rhs)) }
+    fn sub(self, rhs: &Amount) -> Self { self.with(|s| s.sub_assign(rhs)) }
 }
 
-impl Mul<Amount> for Amount {
+impl Mul<&Amount> for Amount {
     type Output = Self;
 
-    fn mul(self, rhs: Self) -> Self { self.with(|s| s.mul_assign(rhs)) }
+    fn mul(self, rhs: &Amount) -> Self { self.with(|s| s.mul_assign(rhs)) }
+}
+
+impl Div<&Amount> for Amount {
+    type Output = Self;
+
+    fn div(self, rhs: &Amount) -> Self { self.with(|s| s.div_assign(rhs)) }
 }
 
 macro_rules! auto_impls {
     ($ty:ty, $op:ident, $fn:ident, $op_asn:ident, $fn_asn:ident) => {
-        impl $op<&$ty> for $ty {
+        impl $op<$ty> for $ty {
             type Output = $ty;
 
             #[inline]
-            fn $fn(self, rhs: &$ty) -> $ty { self.$fn(*rhs) }
+            fn $fn(self, rhs: $ty) -> $ty { self.$fn(&rhs) }
         }
 
         impl<'a> $op<$ty> for &'a $ty {
             type Output = $ty;
 
             #[inline]
-            fn $fn(self, rhs: $ty) -> $ty { (*self).$fn(rhs) }
+            fn $fn(self, rhs: $ty) -> $ty { self.clone().$fn(&rhs) }
         }
 
         impl<'a> $op<&$ty> for &'a $ty {
             type Output = $ty;
 
             #[inline]
-            fn $fn(self, rhs: &$ty) -> $ty { (*self).$fn(*rhs) }
+            fn $fn(self, rhs: &$ty) -> $ty { self.clone().$fn(rhs) }
         }
 
-        impl $op_asn<&$ty> for $ty {
+        impl $op_asn<$ty> for $ty {
             #[inline]
-            fn $fn_asn(&mut self, rhs: &$ty) { self.$fn_asn(*rhs) }
+            fn $fn_asn(&mut self, rhs: $ty) { self.$fn_asn(&rhs) }
         }
     };
 }
@@ -141,3 +322,82 @@ macro_rules! auto_impls {
 auto_impls!(Amount, Add, add, AddAssign, add_assign);
 auto_impls!(Amount, Sub, sub, SubAssign, sub_assign);
 auto_impls!(Amount, Mul, mul, MulAssign, mul_assign);
+auto_impls!(Amount, Div, div, DivAssign, div_assign);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(num: i64, den: i64) -> BigRational { BigRational::new(num.into(), den.into()) }
+
+    #[test]
+    fn parse() {
+        assert_eq!("1/3".parse::<Amount>().unwrap().0, ratio(1, 3));
+        assert_eq!("7.5".parse::<Amount>().unwrap().0, ratio(15, 2));
+        assert_eq!(" 12 ".parse::<Amount>().unwrap().0, ratio(12, 1));
+        assert_eq!(".25".parse::<Amount>().unwrap().0, ratio(1, 4));
+        assert_eq!("1.5/0.5".parse::<Amount>().unwrap().0, ratio(3, 1));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in ["", ".", "abc", "1e5", "1/", "/2", "1/2/3", "1,5"] {
+            assert!(
+                matches!(s.parse::<Amount>(), Err(ParseError::Syntax(_))),
+                "{:?} should not parse",
+                s
+            );
+        }
+
+        assert!(matches!("-1".parse::<Amount>(), Err(ParseError::Syntax(_))));
+        assert!(matches!("1/0".parse::<Amount>(), Err(ParseError::DivideByZero(_))));
+    }
+
+    #[test]
+    fn display() {
+        let amount = |s: &str| s.parse::<Amount>().unwrap();
+
+        assert_eq!(amount("7.5").to_string(), "7.5");
+        assert_eq!(amount("1/3").to_string(), "0.3333");
+        assert_eq!(amount("2/3").to_string(), "0.6667");
+        assert_eq!(amount("12").to_string(), "12");
+        assert_eq!(amount("1/100000").to_string(), "0");
+        assert_eq!(format!("{:.2}", amount("7.5")), "7.50");
+        assert_eq!(format!("{:>6}", amount("7.5")), "   7.5");
+
+        assert_eq!(amount("1/3").to_exact_string(), "1/3");
+        assert_eq!(amount("1/8").to_exact_string(), "0.125");
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        for s in ["0", "12", "7.5", "0.1", "1/3", "1/8", "123456789/1000"] {
+            let amount: Amount = s.parse().unwrap();
+
+            let yaml = serde_yaml::to_string(&amount).unwrap();
+            assert_eq!(serde_yaml::from_str::<Amount>(&yaml).unwrap(), amount, "{}", yaml);
+
+            let json = serde_json::to_string(&amount).unwrap();
+            assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), amount, "{}", json);
+        }
+
+        assert_eq!(serde_json::to_string(&"12".parse::<Amount>().unwrap()).unwrap(), "12");
+        assert_eq!(serde_json::to_string(&"7.5".parse::<Amount>().unwrap()).unwrap(), "7.5");
+        assert_eq!(
+            serde_json::to_string(&"1/3".parse::<Amount>().unwrap()).unwrap(),
+            r#""1/3""#
+        );
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+    }
+
+    #[test]
+    fn approx() {
+        assert_eq!(Amount::from_f64_approx(1.234_567, 3).unwrap().0, ratio(247, 200));
+        assert_eq!(Amount::from_f64_approx(2.0, 0).unwrap().0, ratio(2, 1));
+        assert!(matches!(Amount::from_f64_approx(-1.0, 2), Err(ParseError::Amount(_))));
+
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(Amount::from_f64_approx(f, 2), Err(ParseError::NotFinite(_))));
+        }
+    }
+}
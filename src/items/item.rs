@@ -1,6 +1,11 @@
-use std::ops::{Add, AddAssign, Deref, Sub, SubAssign};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::{Add, AddAssign, Deref, Sub, SubAssign},
+};
 
 use num_traits::identities::Zero;
+use serde::{de, ser};
 use thiserror::Error;
 
 use super::Amount;
@@ -12,7 +17,7 @@ pub struct InvalidItem(String);
 
 easy_atom!(Item, String, InvalidItem, |name| InvalidItem(name));
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ItemStack(pub Item, pub Amount);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -24,7 +29,7 @@ pub struct StackTooLarge;
 
 impl ItemStacks {
     pub fn new(mut stacks: HashMap<Item, Amount>) -> Self {
-        stacks.drain_filter(|_, v| *v == Amount::new(0.0).unwrap());
+        stacks.drain_filter(|_, v| v.is_zero());
 
         Self(stacks)
     }
@@ -32,13 +37,13 @@ impl ItemStacks {
     pub fn empty() -> Self { Self(HashMap::default()) }
 
     pub fn stacks_iter(&self) -> impl Iterator<Item = ItemStack> + '_ {
-        self.iter().map(|(i, a)| ItemStack(*i, *a))
+        self.iter().map(|(i, a)| ItemStack(*i, a.clone()))
     }
 
     pub fn insert_one(&mut self, stack: ItemStack) {
         self.0
             .entry(stack.0)
-            .and_modify(|a| *a += stack.1)
+            .and_modify(|a| *a += &stack.1)
             .or_insert(stack.1);
     }
 
@@ -51,18 +56,18 @@ impl ItemStacks {
             return Ok(());
         }
 
-        let amt = self
+        let emptied = self
             .0
             .get_mut(&stack.0)
             .and_then(|r| {
-                r.checked_sub(stack.1).map(|s| {
+                r.checked_sub(&stack.1).map(|s| {
                     *r = s;
-                    s
+                    r.is_zero()
                 })
             })
             .ok_or(StackTooLarge)?;
 
-        if amt == Amount::zero() {
+        if emptied {
             self.0.remove(&stack.0);
         }
 
@@ -75,7 +80,7 @@ impl ItemStacks {
             .map(|stack| {
                 self.0
                     .get(&stack.0)
-                    .and_then(|r| r.checked_sub(stack.1))
+                    .and_then(|r| r.checked_sub(&stack.1))
                     .ok_or(StackTooLarge)
                     .map(|v| (stack.0, v))
             })
@@ -92,12 +97,46 @@ impl ItemStacks {
     }
 }
 
+impl fmt::Display for ItemStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{} {}", self.1, self.0) }
+}
+
+impl fmt::Display for ItemStacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stacks: Vec<_> = self.stacks_iter().collect();
+        stacks.sort_by_cached_key(|s| s.0.to_string());
+
+        stacks.into_iter().enumerate().try_for_each(|(i, stack)| {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}", stack)
+        })
+    }
+}
+
 impl Deref for ItemStacks {
     type Target = HashMap<Item, Amount>;
 
     fn deref(&self) -> &Self::Target { &self.0 }
 }
 
+impl ser::Serialize for ItemStacks {
+    /// Serializes as a map of item names to amounts, sorted by name
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let stacks: BTreeMap<_, _> = self.iter().map(|(i, a)| (i.to_string(), a)).collect();
+
+        ser::Serialize::serialize(&stacks, ser)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ItemStacks {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        de::Deserialize::deserialize(de).map(Self::new)
+    }
+}
+
 impl AddAssign<ItemStack> for ItemStacks {
     fn add_assign(&mut self, rhs: ItemStack) { self.insert_one(rhs) }
 }
@@ -163,7 +202,7 @@ macro_rules! auto_impls {
 
     ($ty:ty, $i:ty, $op:ident, $fn:ident, $op_asn:ident, $fn_asn:ident) => {
         impl $op_asn<&$i> for $ty {
-            fn $fn_asn(&mut self, rhs: &$i) { self.$fn_asn(*rhs) }
+            fn $fn_asn(&mut self, rhs: &$i) { self.$fn_asn(rhs.clone()) }
         }
 
         impl $op_asn<$ty> for $ty {
@@ -173,7 +212,7 @@ macro_rules! auto_impls {
         impl $op<&$i> for $ty {
             type Output = $ty;
 
-            fn $fn(self, rhs: &$i) -> $ty { self.$fn(*rhs) }
+            fn $fn(self, rhs: &$i) -> $ty { self.$fn(rhs.clone()) }
         }
 
         impl $op<$ty> for $ty {
@@ -1,7 +1,11 @@
 mod amount;
 mod item;
 mod machine;
+mod rate;
+mod recipe;
 
 pub use amount::{Amount, AmountError, ParseError};
 pub use item::{InvalidItem, Item, ItemStack, ItemStacks};
 pub use machine::{InvalidMachine, Machine};
+pub use rate::{litres_to_m3, Rate, TimeUnit};
+pub use recipe::{recipe_key, RecipeName};
@@ -0,0 +1,250 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, ser};
+use thiserror::Error;
+
+use super::{Amount, ParseError};
+
+#[derive(Debug, Error)]
+#[error("Unrecognized time unit {0:?}")]
+pub struct InvalidTimeUnit(String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TimeUnit {
+    Second,
+    #[default]
+    Minute,
+    Hour,
+}
+
+/// The game's data reports fluid amounts in litres, but this crate stores them
+/// in cubic metres
+const LITRES_PER_M3: u64 = 1000;
+
+/// Convert a volume in litres, as the game's data gives it, into cubic metres
+pub fn litres_to_m3(litres: Amount) -> Amount { litres / Amount::from_integer(LITRES_PER_M3) }
+
+/// An amount, optionally given per unit of time, such as `"30/min"`.  Fluid
+/// amounts may be marked as volumes, such as `"120m3/min"` or `"500l"`, and
+/// are stored in cubic metres.  A bare amount of a fluid is also read as cubic
+/// metres, never litres.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rate {
+    amount: Amount,
+    unit: Option<TimeUnit>,
+    volume: bool,
+}
+
+impl TimeUnit {
+    /// The length of this unit, in minutes
+    pub fn minutes(self) -> Amount {
+        match self {
+            Self::Second => Amount::from_integer(1) / Amount::from_integer(60),
+            Self::Minute => Amount::from_integer(1),
+            Self::Hour => Amount::from_integer(60),
+        }
+    }
+
+    /// Convert an amount per minute into an amount per this unit
+    pub fn convert(self, per_min: &Amount) -> Amount { per_min * self.minutes() }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Second => "s",
+            Self::Minute => "min",
+            Self::Hour => "h",
+        })
+    }
+}
+
+impl FromStr for TimeUnit {
+    type Err = InvalidTimeUnit;
+
+    fn from_str(s: &str) -> Result<Self, InvalidTimeUnit> {
+        Ok(match s {
+            "s" | "sec" | "second" | "seconds" => Self::Second,
+            "m" | "min" | "minute" | "minutes" => Self::Minute,
+            "h" | "hr" | "hour" | "hours" => Self::Hour,
+            s => return Err(InvalidTimeUnit(s.to_owned())),
+        })
+    }
+}
+
+impl ser::Serialize for TimeUnit {
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.collect_str(self)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for TimeUnit {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let s: String = de::Deserialize::deserialize(de)?;
+
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Rate {
+    pub fn new(amount: Amount, unit: Option<TimeUnit>) -> Self {
+        Self {
+            amount,
+            unit,
+            volume: false,
+        }
+    }
+
+    /// Mark this rate as a volume of fluid, in cubic metres
+    pub fn in_m3(self) -> Self {
+        Self {
+            volume: true,
+            ..self
+        }
+    }
+
+    pub fn amount(&self) -> &Amount { &self.amount }
+
+    pub fn unit(&self) -> Option<TimeUnit> { self.unit }
+
+    /// Whether this rate was given as a volume of fluid
+    pub fn is_volume(&self) -> bool { self.volume }
+
+    /// Convert this rate to an amount per minute, reading a bare amount as
+    /// being given per `default`
+    pub fn per_min_or(&self, default: TimeUnit) -> Amount {
+        &self.amount / self.unit.unwrap_or(default).minutes()
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let volume = if self.volume { "m3" } else { "" };
+
+        match self.unit {
+            Some(unit) => write!(f, "{}{}/{}", self.amount, volume, unit),
+            None => write!(f, "{}{}", self.amount, volume),
+        }
+    }
+}
+
+/// Parse an amount with an optional volume suffix, converting litres to
+/// cubic metres
+fn parse_volume(s: &str) -> Result<Rate, ParseError> {
+    let s = s.trim();
+
+    if let Some(amt) = s.strip_suffix("m3").or_else(|| s.strip_suffix("m³")) {
+        Ok(Rate::from(amt.parse::<Amount>()?).in_m3())
+    } else if let Some(amt) = s.strip_suffix('l').or_else(|| s.strip_suffix('L')) {
+        Ok(Rate::from(litres_to_m3(amt.parse()?)).in_m3())
+    } else {
+        Ok(Rate::from(s.parse::<Amount>()?))
+    }
+}
+
+impl FromStr for Rate {
+    type Err = ParseError;
+
+    /// Parses an amount with optional volume and unit suffixes, such as
+    /// `"0.5/s"` or `"60m3/min"`, taking care not to mistake a fraction such
+    /// as `"1/3"` for a rate
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let s = s.trim();
+
+        match s.rsplit_once('/') {
+            Some((amount, unit)) => match unit.trim().parse() {
+                Ok(unit) => Ok(Self {
+                    unit: Some(unit),
+                    ..parse_volume(amount)?
+                }),
+                Err(_) => parse_volume(s),
+            },
+            None => parse_volume(s),
+        }
+    }
+}
+
+impl From<Amount> for Rate {
+    fn from(amount: Amount) -> Self { Self::new(amount, None) }
+}
+
+impl<'de> de::Deserialize<'de> for Rate {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Rate;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(r#"a number or a rate, such as 60 or "1/s""#)
+            }
+
+            fn visit_u64<E: de::Error>(self, u: u64) -> Result<Rate, E> {
+                Ok(Amount::from_integer(u).into())
+            }
+
+            fn visit_i64<E: de::Error>(self, i: i64) -> Result<Rate, E> {
+                u64::try_from(i)
+                    .map(|u| Amount::from_integer(u).into())
+                    .map_err(|_| E::custom(format!("Invalid item amount {}", i)))
+            }
+
+            fn visit_f64<E: de::Error>(self, f: f64) -> Result<Rate, E> {
+                if !f.is_finite() {
+                    return Err(E::custom(format!("Invalid item amount {}", f)));
+                }
+
+                format!("{}", f).parse().map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Rate, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        de.deserialize_any(Visitor)
+    }
+}
+
+impl ser::Serialize for Rate {
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let volume = if self.volume { "m3" } else { "" };
+        let amt = self.amount.to_exact_string();
+
+        match self.unit {
+            Some(unit) => ser.serialize_str(&format!("{}{}/{}", amt, volume, unit)),
+            None if self.volume => ser.serialize_str(&format!("{}{}", amt, volume)),
+            None => ser::Serialize::serialize(&self.amount, ser),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(s: &str) -> Rate { s.parse().unwrap() }
+
+    fn amount(s: &str) -> Amount { s.parse().unwrap() }
+
+    #[test]
+    fn parse() {
+        assert_eq!(rate("1/3"), Rate::new(amount("1/3"), None));
+        assert_eq!(rate("30/min"), Rate::new(amount("30"), Some(TimeUnit::Minute)));
+        assert_eq!(rate("1/3/min"), Rate::new(amount("1/3"), Some(TimeUnit::Minute)));
+        assert_eq!(rate(" 0.5 / s "), Rate::new(amount("0.5"), Some(TimeUnit::Second)));
+        assert_eq!(rate("60m3/min"), Rate::new(amount("60"), Some(TimeUnit::Minute)).in_m3());
+        assert_eq!(rate("500l"), Rate::new(amount("0.5"), None).in_m3());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in ["30/fortnight", "30/", "m3/min", "1/3/min/h"] {
+            assert!(
+                matches!(s.parse::<Rate>(), Err(ParseError::Syntax(_))),
+                "{:?} should not parse",
+                s
+            );
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use crate::{easy_atom, prelude::*};
+
+#[derive(Debug, Error)]
+#[error("Unrecognized recipe name {0:?}")]
+pub struct InvalidRecipe(String);
+
+easy_atom!(RecipeName, String, InvalidRecipe, |name| InvalidRecipe(name));
+
+/// Reduce a recipe's display name to the identifier used to refer to it, e.g.
+/// `"Alternate: Pure Iron Ingot"` becomes `alternate_pure_iron_ingot`
+pub fn recipe_key(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
@@ -6,7 +6,10 @@
 mod atom;
 mod cli;
 mod config;
+mod export;
+mod import;
 mod items;
+mod plan;
 mod repl;
 
 pub type HashMap<K, V> = std::collections::HashMap<K, V, ahash::RandomState>;
@@ -26,15 +29,21 @@ fn main() {
     use std::process;
 
     use clap::Parser;
-    use cli::Opts;
+    use cli::{Command, Opts};
 
     let mut b = env_logger::Builder::from_default_env();
     b.filter_module("rustyline", log::LevelFilter::Info);
     b.init();
 
-    let opts = Opts::parse();
+    let mut opts = Opts::parse();
 
-    match repl::run(opts) {
+    let res = match opts.command.take() {
+        Some(Command::Import { docs, out }) => import::run(docs, out),
+        Some(Command::Exec(cmd)) => repl::exec(opts, cmd),
+        None => repl::run(opts),
+    };
+
+    match res {
         Ok(()) => (),
         Err(e) => {
             log::error!("Fatal error: {:?}", e);
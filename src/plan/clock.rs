@@ -0,0 +1,164 @@
+use std::{fmt, str::FromStr};
+
+use num_traits::{One, Zero};
+use serde::{de, ser};
+use thiserror::Error;
+
+use crate::items::{Amount, ParseError};
+
+/// The exponent of the game's power curve, log2(2.5), so that doubling a
+/// machine's clock speed multiplies its power draw by 2.5
+const POWER_EXPONENT: f64 = 1.321_928;
+
+#[derive(Debug, Error)]
+pub enum ClockError {
+    #[error("Invalid clock speed: {0}")]
+    Parse(#[from] ParseError),
+    #[error("Clock speed {0}% is outside the range 1% to 250%")]
+    Range(Amount),
+}
+
+/// A machine's clock speed, as a fraction of its base speed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClockSpeed(Amount);
+
+impl ClockSpeed {
+    pub fn new(fraction: Amount) -> Result<Self, ClockError> {
+        let pct = &fraction * Amount::from_integer(100);
+
+        if pct < Amount::one() || pct > Amount::from_integer(250) {
+            return Err(ClockError::Range(pct));
+        }
+
+        Ok(Self(fraction))
+    }
+
+    pub fn full() -> Self { Self(Amount::one()) }
+
+    pub fn min() -> Self { Self(Amount::one() / Amount::from_integer(100)) }
+
+    pub fn max() -> Self { Self(Amount::from_integer(5) / Amount::from_integer(2)) }
+
+    /// The multiplier this clock speed applies to a machine's cycle rate
+    pub fn factor(&self) -> &Amount { &self.0 }
+
+    /// The multiplier this clock speed applies to a machine's power draw,
+    /// rounded to the nearest millionth
+    pub fn power_factor(&self) -> Amount {
+        if self.0 == Amount::one() {
+            return Amount::one();
+        }
+
+        Amount::from_f64_approx(self.0.to_f64().powf(POWER_EXPONENT), 6)
+            .expect("Clock speeds are always positive")
+    }
+
+    /// Spread the work of `machines` machines running at this speed evenly
+    /// over a whole number of machines, returning the number of machines to
+    /// build and the speed to underclock them to
+    pub fn spread(&self, machines: &Amount) -> (Amount, ClockSpeed) {
+        let built = machines.ceil();
+
+        if built.is_zero() {
+            return (built, self.clone());
+        }
+
+        // Less work than one machine does at 1% still takes one machine at 1%
+        let clock = ClockSpeed::new(machines * &self.0 / &built).unwrap_or_else(|_| Self::min());
+
+        (built, clock)
+    }
+
+    /// Split `machines` machines running at this speed into a number of whole
+    /// machines and, if needed, the speed of one last machine running the rest.
+    /// A remainder too small to run at 1% is taken on by the last whole
+    /// machine where that stays within 250%, and is otherwise run at 1%.
+    pub fn split(&self, machines: &Amount) -> (Amount, Option<ClockSpeed>) {
+        let whole = machines.floor();
+        let part = machines.checked_sub(&whole).unwrap();
+
+        if part.is_zero() {
+            return (whole, None);
+        }
+
+        if let Ok(clock) = ClockSpeed::new(&part * &self.0) {
+            return (whole, Some(clock));
+        }
+
+        let folded = whole.checked_sub(&Amount::one()).and_then(|rest| {
+            let clock = ClockSpeed::new((part + Amount::one()) * &self.0).ok()?;
+
+            Some((rest, Some(clock)))
+        });
+
+        folded.unwrap_or_else(|| (whole, Some(Self::min())))
+    }
+}
+
+impl fmt::Display for ClockSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}%", &self.0 * Amount::from_integer(100))
+    }
+}
+
+impl FromStr for ClockSpeed {
+    type Err = ClockError;
+
+    /// Parses a percentage, with or without a trailing `%`
+    fn from_str(s: &str) -> Result<Self, ClockError> {
+        let pct: Amount = s.trim().trim_end_matches('%').parse()?;
+
+        Self::new(pct / Amount::from_integer(100))
+    }
+}
+
+impl ser::Serialize for ClockSpeed {
+    /// Serializes as an exact percentage, such as `"250%"` or `"1200/13%"`
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let pct = &self.0 * Amount::from_integer(100);
+
+        ser.serialize_str(&format!("{}%", pct.to_exact_string()))
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ClockSpeed {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let s: String = de::Deserialize::deserialize(de)?;
+
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(s: &str) -> Amount { s.parse().unwrap() }
+
+    fn clock(s: &str) -> ClockSpeed { s.parse().unwrap() }
+
+    #[test]
+    fn split() {
+        assert_eq!(clock("100").split(&amount("3")), (amount("3"), None));
+        assert_eq!(clock("100").split(&amount("2.5")), (amount("2"), Some(clock("50"))));
+        assert_eq!(clock("200").split(&amount("1/4")), (amount("0"), Some(clock("50"))));
+    }
+
+    #[test]
+    fn split_tiny_remainder() {
+        // 0.5% is too slow, so the last whole machine runs 100.5% instead
+        assert_eq!(clock("100").split(&amount("2.005")), (amount("1"), Some(clock("100.5"))));
+
+        // The last whole machine can't go past 250%, so the rest runs at 1%
+        assert_eq!(clock("250").split(&amount("2.001")), (amount("2"), Some(ClockSpeed::min())));
+
+        // With no whole machine to take it on, the rest runs at 1%
+        assert_eq!(clock("100").split(&amount("0.001")), (amount("0"), Some(ClockSpeed::min())));
+    }
+
+    #[test]
+    fn spread() {
+        assert_eq!(clock("100").spread(&amount("2.5")), (amount("3"), ClockSpeed(amount("5/6"))));
+        assert_eq!(clock("100").spread(&amount("0.001")), (amount("1"), ClockSpeed::min()));
+    }
+}
@@ -0,0 +1,330 @@
+use std::sync::Arc;
+
+use num_rational::BigRational;
+use num_traits::Zero;
+
+use super::{RecipeRate, Settings};
+use crate::{
+    config::{Config, Recipe},
+    items::{Item, ItemStacks},
+    HashMap, HashSet,
+};
+
+/// A set of items that can each be made, directly or indirectly, from any
+/// other, along with the enabled recipes linking them
+#[derive(Debug, Clone)]
+pub struct Cycle {
+    items: Vec<Item>,
+    recipes: Vec<Arc<Recipe>>,
+}
+
+impl Cycle {
+    pub fn items(&self) -> &[Item] { &self.items }
+
+    pub fn recipes(&self) -> &[Arc<Recipe>] { &self.recipes }
+}
+
+/// A loop among the recipes a plan uses, with the net rate its recipes make
+/// each of its items at
+#[derive(Debug, Clone)]
+pub struct PlanCycle {
+    items: Vec<(Item, BigRational)>,
+    recipes: Vec<usize>,
+}
+
+impl PlanCycle {
+    /// Each item in the loop with its net rate per minute, negative where the
+    /// loop uses more of it than it makes
+    pub fn items(&self) -> &[(Item, BigRational)] { &self.items }
+
+    /// The indices of the loop's recipes in the plan
+    pub fn recipes(&self) -> &[usize] { &self.recipes }
+}
+
+/// Tarjan's algorithm over the graph with an edge from each input of an
+/// enabled recipe to each of its outputs
+struct Search {
+    edges: HashMap<Item, Vec<Item>>,
+    index: HashMap<Item, (usize, usize)>,
+    stack: Vec<Item>,
+    on_stack: HashSet<Item>,
+    components: Vec<Vec<Item>>,
+}
+
+impl Search {
+    /// Visit every item reachable from `root` that hasn't been visited yet.
+    /// Recipe graphs can be deep, so rather than recursing this keeps its own
+    /// stack of the items being visited, each with the next edge to follow.
+    fn visit(&mut self, root: Item) {
+        let mut path = vec![(root, 0)];
+        self.open(root);
+
+        while let Some((item, edge)) = path.last_mut() {
+            let item = *item;
+
+            if let Some(&next) = self.edges.get(&item).and_then(|e| e.get(*edge)) {
+                *edge += 1;
+
+                match self.index.get(&next) {
+                    None => {
+                        self.open(next);
+                        path.push((next, 0));
+                    },
+                    Some(&(next_idx, _)) if self.on_stack.contains(&next) => {
+                        self.lower(item, next_idx);
+                    },
+                    Some(_) => (),
+                }
+
+                continue;
+            }
+
+            path.pop();
+            self.close(item);
+
+            if let Some(&(parent, _)) = path.last() {
+                let low = self.index[&item].1;
+                self.lower(parent, low);
+            }
+        }
+    }
+
+    fn open(&mut self, item: Item) {
+        let idx = self.index.len();
+        self.index.insert(item, (idx, idx));
+        self.stack.push(item);
+        self.on_stack.insert(item);
+    }
+
+    fn lower(&mut self, item: Item, low: usize) {
+        let entry = self.index.get_mut(&item).unwrap();
+        entry.1 = entry.1.min(low);
+    }
+
+    /// Pop the component rooted at `item` off the stack, if it is a root
+    fn close(&mut self, item: Item) {
+        let (idx, low) = self.index[&item];
+
+        if idx != low {
+            return;
+        }
+
+        let mut component = vec![];
+
+        while let Some(top) = self.stack.pop() {
+            self.on_stack.remove(&top);
+            component.push(top);
+
+            if top == item {
+                break;
+            }
+        }
+
+        self.components.push(component);
+    }
+}
+
+/// Find every loop in the graph of enabled recipes
+pub fn find(config: &Config, settings: &Settings) -> Vec<Cycle> {
+    let recipes: Vec<_> = config
+        .recipes()
+        .iter()
+        .filter(|r| settings.is_enabled(r))
+        .collect();
+
+    search(&recipes)
+}
+
+/// Find every loop among the recipes used by a plan, along with the net rate
+/// of each item in it
+pub fn in_plan(rates: &[RecipeRate]) -> Vec<PlanCycle> {
+    let recipes: Vec<_> = rates.iter().map(RecipeRate::recipe).collect();
+
+    search(&recipes)
+        .into_iter()
+        .map(|cycle| {
+            let mut indices: Vec<_> = cycle
+                .recipes
+                .iter()
+                .filter_map(|r| rates.iter().position(|p| Arc::ptr_eq(p.recipe(), r)))
+                .collect();
+            indices.sort_unstable();
+
+            let items = cycle
+                .items
+                .into_iter()
+                .map(|item| (item, indices.iter().map(|i| net_rate(&rates[*i], item)).sum()))
+                .collect();
+
+            PlanCycle {
+                items,
+                recipes: indices,
+            }
+        })
+        .collect()
+}
+
+/// The net rate per minute a planned recipe makes an item at, negative if it
+/// uses more than it makes
+fn net_rate(rate: &RecipeRate, item: Item) -> BigRational {
+    let per_min = rate.cycles_per_min();
+    let amount = |stacks: &ItemStacks| {
+        stacks
+            .get(&item)
+            .map_or_else(BigRational::zero, |a| (a * &per_min).into_ratio())
+    };
+
+    amount(rate.recipe().outputs()) - amount(rate.recipe().inputs())
+}
+
+fn search(recipes: &[&Arc<Recipe>]) -> Vec<Cycle> {
+    let mut edges: HashMap<Item, Vec<Item>> = HashMap::default();
+
+    for recipe in recipes {
+        for input in recipe.inputs().keys() {
+            edges
+                .entry(*input)
+                .or_default()
+                .extend(recipe.outputs().keys().copied());
+        }
+    }
+
+    let mut nodes: Vec<_> = edges.keys().copied().collect();
+    nodes.sort_by_cached_key(ToString::to_string);
+
+    let mut search = Search {
+        edges,
+        index: HashMap::default(),
+        stack: vec![],
+        on_stack: HashSet::default(),
+        components: vec![],
+    };
+
+    for item in nodes {
+        if !search.index.contains_key(&item) {
+            search.visit(item);
+        }
+    }
+
+    let mut cycles: Vec<_> = search
+        .components
+        .into_iter()
+        .filter_map(|mut items| {
+            let linked: Vec<_> = recipes
+                .iter()
+                .filter(|r| {
+                    r.inputs().keys().any(|i| items.contains(i))
+                        && r.outputs().keys().any(|o| items.contains(o))
+                })
+                .map(|r| Arc::clone(r))
+                .collect();
+
+            // A lone item is only a cycle if some recipe makes it from itself
+            if linked.is_empty() {
+                return None;
+            }
+
+            items.sort_by_cached_key(ToString::to_string);
+
+            Some(Cycle {
+                items,
+                recipes: linked,
+            })
+        })
+        .collect();
+
+    cycles.sort_by_cached_key(|c| c.items[0].to_string());
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::tests::load,
+        items::{Amount, ItemStacks, TimeUnit},
+        plan::{Objective, Plan},
+    };
+
+    // Rubber and plastic can each be recycled from the other
+    const CONFIG: &str = "
+raw:
+  - fuel
+  - crude_oil
+machines:
+  constructor: { power: 4 }
+  refinery: { power: 30 }
+recipes:
+  constructor:
+    - { make: 1 screw, from: 1 plastic, in: 60 }
+  refinery:
+    - { make: 1 plastic, from: 1 crude_oil, in: 60, name: Plastic }
+    - { make: 2 rubber, from: 1 plastic 1 fuel, in: 60, name: Recycled Rubber }
+    - { make: 2 plastic, from: 1 rubber 1 fuel, in: 60, name: Recycled Plastic }
+";
+
+    fn config() -> Config { load(CONFIG).unwrap() }
+
+    fn item(name: &str) -> Item { Item::new(name).unwrap() }
+
+    #[test]
+    fn find_loop() {
+        let config = config();
+        let cycles = find(&config, &Settings::default());
+
+        let [cycle] = &cycles[..] else {
+            panic!("Expected one cycle, got {:?}", cycles);
+        };
+
+        assert_eq!(cycle.items(), [item("plastic"), item("rubber")]);
+
+        let mut recipes: Vec<_> = cycle.recipes().iter().map(ToString::to_string).collect();
+        recipes.sort();
+        assert_eq!(recipes, ["Recycled Plastic", "Recycled Rubber"]);
+    }
+
+    #[test]
+    fn net_rates() {
+        let config = config();
+        let mut settings = Settings::default();
+        settings.set_enabled("plastic".parse().unwrap(), false);
+
+        let want = ItemStacks::new(
+            [(item("plastic"), Amount::from_integer(10))]
+                .into_iter()
+                .collect(),
+        );
+        let have = ItemStacks::empty();
+        let plan = Plan::calculate(&config, &want, &have, &settings, Objective::Raw).unwrap();
+
+        let [cycle] = plan.cycles() else {
+            panic!("Expected one cycle, got {:?}", plan.cycles());
+        };
+
+        let recipes: Vec<_> = cycle
+            .recipes()
+            .iter()
+            .map(|i| plan.recipes()[*i].recipe().to_string())
+            .collect();
+
+        assert_eq!(recipes, ["Recycled Plastic", "Recycled Rubber"]);
+        assert_eq!(cycle.items(), [
+            (item("plastic"), BigRational::from_integer(10.into())),
+            (item("rubber"), BigRational::zero()),
+        ]);
+
+        let shown = plan.display(&config, TimeUnit::Minute).to_string();
+        let loops = shown.split("Loops:\n").nth(1).unwrap();
+
+        assert!(
+            loops.starts_with(concat!(
+                "  refinery (Recycled Plastic), refinery (Recycled Rubber):\n",
+                "    +10 plastic/min net\n",
+                "    0 rubber/min net\n",
+            )),
+            "{}",
+            shown
+        );
+    }
+}
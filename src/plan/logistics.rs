@@ -0,0 +1,228 @@
+use std::fmt;
+
+use num_traits::{One, Zero};
+
+use super::{Plan, Settings};
+use crate::{
+    config::{Config, Conveyor},
+    items::{Amount, Item, ItemStacks},
+    HashMap,
+};
+
+/// One end of an edge in a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Node {
+    /// A raw resource extracted from the world
+    Raw(Item),
+    /// One of the available inputs
+    Available(Item),
+    /// The machines running a recipe, by its index in the plan
+    Recipe(usize),
+    /// One of the requested outputs
+    Output(Item),
+    /// Excess left over for use elsewhere
+    Surplus(Item),
+    /// Excess destroyed in a sink
+    Sink(Item),
+}
+
+/// The belts or pipes needed to carry some rate of an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lines {
+    conveyor: Conveyor,
+    tier: String,
+    count: Amount,
+}
+
+/// A flow of one item from where it is produced to where it is used
+#[derive(Debug, Clone)]
+pub struct Edge {
+    from: Node,
+    to: Node,
+    item: Item,
+    rate: Amount,
+    lines: Lines,
+}
+
+impl Lines {
+    /// The slowest unlocked tier that carries `per_min` of `item` in one line,
+    /// or else the number of lines of the highest unlocked tier needed
+    pub fn carrying(config: &Config, settings: &Settings, item: Item, per_min: &Amount) -> Self {
+        let conveyor = config.item_form(item).conveyor();
+        let tiers = config
+            .logistics()
+            .unlocked(conveyor, settings.max_tier(conveyor));
+        let best = tiers.last().expect("Tier tables are never empty");
+
+        match tiers.iter().find(|t| per_min <= t.capacity()) {
+            Some(tier) => Self {
+                conveyor,
+                tier: tier.name().to_owned(),
+                count: Amount::one(),
+            },
+            None => Self {
+                conveyor,
+                tier: best.name().to_owned(),
+                count: (per_min / best.capacity()).ceil(),
+            },
+        }
+    }
+
+    pub fn conveyor(&self) -> Conveyor { self.conveyor }
+
+    pub fn tier(&self) -> &str { &self.tier }
+
+    pub fn count(&self) -> &Amount { &self.count }
+
+    /// Whether this rate needs more than one line of the highest unlocked tier
+    pub fn is_over(&self) -> bool { self.count > Amount::one() }
+}
+
+impl fmt::Display for Lines {
+    /// Formats the lines needed, e.g. `2 mk5 belts`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}{}",
+            self.count,
+            self.tier,
+            self.conveyor,
+            if self.is_over() { "s" } else { "" }
+        )
+    }
+}
+
+impl Edge {
+    pub fn from(&self) -> Node { self.from }
+
+    pub fn to(&self) -> Node { self.to }
+
+    pub fn item(&self) -> Item { self.item }
+
+    /// The rate of the item carried, per minute
+    pub fn rate(&self) -> &Amount { &self.rate }
+
+    /// The belts or pipes this edge needs
+    pub fn lines(&self) -> &Lines { &self.lines }
+}
+
+/// The stacks of each item produced or used by the nodes of a plan
+#[derive(Debug, Default)]
+struct Ends(HashMap<Item, Vec<(Node, Amount)>>);
+
+impl Ends {
+    fn add(&mut self, item: Item, node: Node, amt: Amount) {
+        if !amt.is_zero() {
+            self.0.entry(item).or_default().push((node, amt));
+        }
+    }
+
+    fn add_stacks(&mut self, stacks: &ItemStacks, node: fn(Item) -> Node) {
+        let mut stacks: Vec<_> = stacks.stacks_iter().collect();
+        stacks.sort_by_cached_key(|s| s.0.to_string());
+
+        for stack in stacks {
+            self.add(stack.0, node(stack.0), stack.1);
+        }
+    }
+}
+
+/// Route every item in a plan from its producers to its consumers, splitting
+/// each producer's output between consumers in proportion to their use, and
+/// find the belts or pipes each edge needs.
+///
+/// Edges join whole groups of machines running the same recipe, so their rates
+/// and lines are totals for the group, not for each machine. Any share of a
+/// recipe's output it consumes itself stays inside its group and gets no edge.
+pub fn route(config: &Config, settings: &Settings, plan: &Plan) -> Vec<Edge> {
+    let mut sources = Ends::default();
+    let mut sinks = Ends::default();
+
+    sources.add_stacks(&plan.inputs, Node::Raw);
+    sources.add_stacks(&plan.used, Node::Available);
+
+    for (i, rate) in plan.recipes.iter().enumerate() {
+        let cycles = rate.cycles_per_min();
+
+        for stack in rate.recipe.outputs().stacks_iter() {
+            sources.add(stack.0, Node::Recipe(i), stack.1 * &cycles);
+        }
+
+        for stack in rate.recipe.inputs().stacks_iter() {
+            sinks.add(stack.0, Node::Recipe(i), stack.1 * &cycles);
+        }
+    }
+
+    sinks.add_stacks(&plan.outputs, Node::Output);
+    sinks.add_stacks(&plan.surplus, Node::Surplus);
+    sinks.add_stacks(&plan.sunk, Node::Sink);
+
+    let mut items: Vec<_> = sources.0.keys().copied().collect();
+    items.sort_by_cached_key(ToString::to_string);
+
+    let mut edges = vec![];
+
+    for item in items {
+        let from = &sources.0[&item];
+        let to = sinks.0.get(&item).map_or(&[][..], Vec::as_slice);
+        let total = to.iter().fold(Amount::zero(), |s, (_, a)| s + a);
+
+        if total.is_zero() {
+            continue;
+        }
+
+        for (src, made) in from {
+            for (dst, used) in to.iter().filter(|(d, _)| d != src) {
+                let rate = made * used / &total;
+
+                edges.push(Edge {
+                    from: *src,
+                    to: *dst,
+                    item,
+                    lines: Lines::carrying(config, settings, item, &rate),
+                    rate,
+                });
+            }
+        }
+    }
+
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::tests::load, plan::Objective};
+
+    // Seeds are planted to grow more seeds
+    const CONFIG: &str = "
+raw:
+  - water
+machines:
+  farm: { power: 1 }
+recipes:
+  farm:
+    - { make: 2 seed, from: 1 seed 1 water, in: 60 }
+";
+
+    #[test]
+    fn skip_self_edges() {
+        let config = load(CONFIG).unwrap();
+        let seed = Item::new("seed").unwrap();
+        let want = ItemStacks::new([(seed, Amount::from_integer(10))].into_iter().collect());
+        let have = ItemStacks::empty();
+        let plan =
+            Plan::calculate(&config, &want, &have, &Settings::default(), Objective::Raw).unwrap();
+
+        assert!(plan.edges().iter().all(|e| e.from() != e.to()), "{:?}", plan.edges());
+
+        let out: Vec<_> = plan
+            .edges()
+            .iter()
+            .filter(|e| e.to() == Node::Output(seed))
+            .map(|e| (e.from(), e.rate().clone()))
+            .collect();
+
+        assert_eq!(out, [(Node::Recipe(0), Amount::from_integer(10))]);
+    }
+}
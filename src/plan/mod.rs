@@ -0,0 +1,392 @@
+mod clock;
+pub mod cycles;
+mod logistics;
+mod model;
+mod report;
+mod settings;
+mod simplex;
+mod target;
+
+use std::{fmt, sync::Arc};
+
+pub use clock::ClockSpeed;
+use cycles::PlanCycle;
+pub use logistics::{Edge, Node};
+use model::Model;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+pub use report::{Report, SCHEMA_VERSION};
+pub use settings::{ByproductPolicy, ClockTarget, Objective, Settings};
+pub use target::Target;
+
+use crate::{
+    config::{Config, Extraction, Recipe},
+    items::{Amount, Item, ItemStacks, Machine, TimeUnit},
+    prelude::*,
+    HashMap,
+};
+
+#[derive(Debug, Clone)]
+pub struct RecipeRate {
+    recipe: Arc<Recipe>,
+    machines: Amount,
+    clock: ClockSpeed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    recipes: Vec<RecipeRate>,
+    machines: HashMap<Machine, Amount>,
+    power: HashMap<Machine, Amount>,
+    inputs: ItemStacks,
+    used: ItemStacks,
+    extraction: Vec<(Item, Extraction)>,
+    outputs: ItemStacks,
+    surplus: ItemStacks,
+    sunk: ItemStacks,
+    edges: Vec<Edge>,
+    cycles: Vec<PlanCycle>,
+    alternates: Vec<Arc<Recipe>>,
+}
+
+impl Plan {
+    /// Compute the rates of the enabled recipes producing `want`, given in
+    /// items per minute, minimizing `objective` and then any other objective.
+    /// Items in `have` are used before any raw resources are extracted.
+    pub fn calculate(
+        config: &Config,
+        want: &ItemStacks,
+        have: &ItemStacks,
+        settings: &Settings,
+        objective: Objective,
+    ) -> Result<Self> {
+        check_producible(config, want.keys().copied(), have, settings)?;
+
+        let mut model = Model::new(config, have, settings, objective);
+        model.require(want);
+
+        let solution = model
+            .solve(objective)
+            .context("Failed to solve for the requested outputs")?;
+
+        model.into_plan(config, want, &solution)
+    }
+
+    /// Compute the rates of the enabled recipes producing as much of the
+    /// targets as possible, along with `want`, from only the items in `have`
+    pub fn maximize(
+        config: &Config,
+        targets: &[Target],
+        want: &ItemStacks,
+        have: &ItemStacks,
+        settings: &Settings,
+        objective: Objective,
+    ) -> Result<Self> {
+        if have.is_empty() {
+            bail!("No inputs are available to make anything from");
+        }
+
+        check_producible(config, targets.iter().map(Target::item), have, settings)?;
+        check_producible(config, want.keys().copied(), have, settings)?;
+
+        let mut model = Model::new(config, have, settings, objective);
+        model.maximize(targets);
+        model.require(want);
+
+        let solution = model
+            .solve(objective)
+            .context("Failed to solve for the most output from the available inputs")?;
+
+        model.into_plan(config, want, &solution)
+    }
+
+    pub fn display<'a>(&'a self, config: &'a Config, unit: TimeUnit) -> DisplayPlan<'a> {
+        DisplayPlan(self, config, unit)
+    }
+
+    /// Collect the plan into a serializable report with rates given per `unit`
+    pub fn report(&self, config: &Config, unit: TimeUnit) -> Report {
+        Report::new(self, config, unit)
+    }
+
+    /// The power drawn by every machine in the plan, in MW
+    pub fn total_power(&self) -> Amount {
+        self.power.values().fold(Amount::zero(), |s, p| s + p)
+    }
+
+    /// The recipes used, sorted by machine and then by recipe
+    pub fn recipes(&self) -> &[RecipeRate] { &self.recipes }
+
+    /// The number of each machine running, at the recipes' planned clock speeds
+    pub fn machines(&self) -> &HashMap<Machine, Amount> { &self.machines }
+
+    /// The power drawn by each type of machine, in MW
+    pub fn power(&self) -> &HashMap<Machine, Amount> { &self.power }
+
+    /// The raw resources extracted, per minute
+    pub fn inputs(&self) -> &ItemStacks { &self.inputs }
+
+    /// The available inputs used, per minute
+    pub fn used(&self) -> &ItemStacks { &self.used }
+
+    /// The wanted outputs, and any maximized targets, per minute
+    pub fn outputs(&self) -> &ItemStacks { &self.outputs }
+
+    /// Excess production left over, per minute
+    pub fn surplus(&self) -> &ItemStacks { &self.surplus }
+
+    /// Excess production destroyed in sinks, per minute
+    pub fn sunk(&self) -> &ItemStacks { &self.sunk }
+
+    /// The nodes mined for each raw resource with known nodes
+    pub fn extraction(&self) -> &[(Item, Extraction)] { &self.extraction }
+
+    /// Every flow of an item between two parts of the plan, in order of item
+    /// name and then of where the item comes from and goes to
+    pub fn edges(&self) -> &[Edge] { &self.edges }
+
+    /// Every loop among the recipes used
+    pub fn cycles(&self) -> &[PlanCycle] { &self.cycles }
+
+    /// The alternate recipes enabled when the plan was calculated, by name
+    pub fn alternates(&self) -> &[Arc<Recipe>] { &self.alternates }
+
+    /// Describe one end of an edge briefly
+    pub fn node_name(&self, config: &Config, node: Node) -> String {
+        match node {
+            Node::Raw(_) => "raw".into(),
+            Node::Available(_) => "available".into(),
+            Node::Recipe(i) => {
+                let rate = &self.recipes[i];
+
+                format!("{} ({})", rate.recipe.machine(), config.describe(&rate.recipe))
+            },
+            Node::Output(_) => "output".into(),
+            Node::Surplus(_) => "surplus".into(),
+            Node::Sink(_) => "sink".into(),
+        }
+    }
+}
+
+/// Fail early, with a clearer error than the solver's, if any of `items` has
+/// no possible source
+fn check_producible(
+    config: &Config,
+    items: impl IntoIterator<Item = Item>,
+    have: &ItemStacks,
+    settings: &Settings,
+) -> Result<()> {
+    for item in items {
+        let producible = config
+            .keyed_recipes()
+            .get(&item)
+            .into_iter()
+            .flatten()
+            .any(|k| settings.is_enabled(k.recipe()));
+
+        if !(producible || config.raw().contains_key(&item) || have.contains_key(&item)) {
+            bail!(
+                "No enabled recipe produces {} and it is not a raw resource or available input",
+                item
+            );
+        }
+    }
+
+    Ok(())
+}
+
+impl RecipeRate {
+    pub fn recipe(&self) -> &Arc<Recipe> { &self.recipe }
+
+    /// The number of times this recipe completes per minute
+    pub fn cycles_per_min(&self) -> Amount {
+        &self.machines * self.recipe.cycles_per_min() * self.clock.factor()
+    }
+
+    /// The number of machines to build for this recipe, and the clock speed
+    /// to underclock all of them to so they still meet the planned rate
+    pub fn build(&self) -> (Amount, ClockSpeed) { self.clock.spread(&self.machines) }
+
+    /// The power drawn running this recipe as whole machines at the planned
+    /// clock speed plus one underclocked machine for any remainder
+    pub fn power(&self, base: &Amount) -> Amount {
+        let (whole, part) = self.clock.split(&self.machines);
+
+        whole * base * self.clock.power_factor()
+            + part.map_or_else(Amount::zero, |p| base * p.power_factor())
+    }
+}
+
+impl fmt::Display for RecipeRate {
+    /// Formats the machines needed, e.g. `2 at 100% + 1 at 50%`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.clock.split(&self.machines) {
+            (whole, None) => write!(f, "{} at {}", whole, self.clock),
+            (whole, Some(part)) if whole.is_zero() => write!(f, "1 at {}", part),
+            (whole, Some(part)) => write!(f, "{} at {} + 1 at {}", whole, self.clock, part),
+        }
+    }
+}
+
+/// A [`Plan`] formatted with its rates given per a chosen unit of time
+#[derive(Debug)]
+pub struct DisplayPlan<'a>(&'a Plan, &'a Config, TimeUnit);
+
+/// Write a titled list of item rates, using the items' display names
+pub fn write_rates(
+    f: &mut impl fmt::Write,
+    config: &Config,
+    title: &str,
+    stacks: &ItemStacks,
+    unit: TimeUnit,
+) -> fmt::Result {
+    if stacks.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "{}:", title)?;
+
+    let mut stacks: Vec<_> = stacks
+        .iter()
+        .map(|(i, a)| (config.item_name(*i), config.item_form(*i), a))
+        .collect();
+    stacks.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+    stacks.into_iter().try_for_each(|(name, form, amt)| {
+        writeln!(f, "  {}{} {}/{}", unit.convert(amt), form.unit(), name, unit)
+    })
+}
+
+/// Format a net rate per minute with its sign, e.g. `+30` or `-7.5`
+fn signed(unit: TimeUnit, net: &BigRational) -> String {
+    let amt = unit.convert(&Amount::new(net.abs()).expect("Absolute values are non-negative"));
+
+    if net.is_positive() {
+        format!("+{}", amt)
+    } else if net.is_negative() {
+        format!("-{}", amt)
+    } else {
+        amt.to_string()
+    }
+}
+
+impl fmt::Display for DisplayPlan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self(plan, config, unit) = *self;
+
+        writeln!(f, "Recipes:")?;
+
+        for rate in &plan.recipes {
+            writeln!(
+                f,
+                "  {} {} ({}): {} ({} cycles/{})",
+                rate.machines,
+                rate.recipe.machine(),
+                rate,
+                config.describe(&rate.recipe),
+                unit.convert(&rate.cycles_per_min()),
+                unit,
+            )?;
+        }
+
+        writeln!(f, "Build:")?;
+
+        for rate in &plan.recipes {
+            let (built, clock) = rate.build();
+
+            writeln!(
+                f,
+                "  {} {} at {}: {}",
+                built,
+                rate.recipe.machine(),
+                clock,
+                config.describe(&rate.recipe),
+            )?;
+        }
+
+        writeln!(f, "Machines:")?;
+
+        let mut machines: Vec<_> = plan.machines.iter().collect();
+        machines.sort_by_cached_key(|(m, _)| m.to_string());
+
+        for (machine, count) in machines {
+            writeln!(f, "  {} {}", count, machine)?;
+        }
+
+        writeln!(f, "Power:")?;
+
+        let mut power: Vec<_> = plan.power.iter().collect();
+        power.sort_by_cached_key(|(m, _)| m.to_string());
+
+        for (machine, mw) in power {
+            writeln!(f, "  {} MW {}", mw, machine)?;
+        }
+
+        writeln!(f, "  {} MW total", plan.total_power())?;
+
+        write_rates(f, config, "Available inputs used", &plan.used, unit)?;
+        write_rates(f, config, "Raw inputs", &plan.inputs, unit)?;
+
+        if !plan.extraction.is_empty() {
+            writeln!(f, "Resource nodes:")?;
+
+            for (item, extraction) in &plan.extraction {
+                writeln!(f, "  {}: {}", config.item_name(*item), extraction)?;
+            }
+        }
+
+        write_rates(f, config, "Outputs", &plan.outputs, unit)?;
+        write_rates(f, config, "Surplus", &plan.surplus, unit)?;
+        write_rates(f, config, "Sunk", &plan.sunk, unit)?;
+
+        if !plan.cycles.is_empty() {
+            writeln!(f, "Loops:")?;
+
+            for cycle in &plan.cycles {
+                let recipes: Vec<_> = cycle
+                    .recipes()
+                    .iter()
+                    .map(|i| plan.node_name(config, Node::Recipe(*i)))
+                    .collect();
+
+                writeln!(f, "  {}:", recipes.join(", "))?;
+
+                for (item, net) in cycle.items() {
+                    writeln!(
+                        f,
+                        "    {}{} {}/{} net",
+                        signed(unit, net),
+                        config.item_form(*item).unit(),
+                        config.item_name(*item),
+                        unit,
+                    )?;
+                }
+            }
+        }
+
+        if !plan.edges.is_empty() {
+            writeln!(f, "Logistics:")?;
+
+            for edge in &plan.edges {
+                writeln!(
+                    f,
+                    "  {}{} {}/{}: {} -> {} on {}{}",
+                    unit.convert(edge.rate()),
+                    config.item_form(edge.item()).unit(),
+                    config.item_name(edge.item()),
+                    unit,
+                    plan.node_name(config, edge.from()),
+                    plan.node_name(config, edge.to()),
+                    edge.lines(),
+                    if edge.lines().is_over() {
+                        " (exceeds the highest unlocked tier)"
+                    } else {
+                        ""
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
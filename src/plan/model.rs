@@ -0,0 +1,552 @@
+use std::sync::Arc;
+
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+use super::{
+    cycles, logistics,
+    simplex::{Problem, Relation},
+    ByproductPolicy, ClockSpeed, Objective, Plan, RecipeRate, Settings, Target,
+};
+use crate::{
+    config::{Config, Recipe},
+    items::{Amount, Item, ItemStack, ItemStacks},
+    prelude::*,
+    HashMap,
+};
+
+type Terms = Vec<(usize, BigRational)>;
+
+/// The linear program behind a plan, along with what each of its variables
+/// stands for
+#[derive(Debug)]
+pub struct Model<'a> {
+    problem: Problem,
+    flows: HashMap<Item, Terms>,
+    recipes: Vec<(&'a Arc<Recipe>, &'a ClockSpeed, usize)>,
+    raw: Vec<(Item, usize)>,
+    supply: Vec<(Item, usize)>,
+    targets: Vec<Item>,
+    goal: Terms,
+    power_cost: Terms,
+    building_cost: Terms,
+    settings: &'a Settings,
+}
+
+impl<'a> Model<'a> {
+    pub fn new(
+        config: &'a Config,
+        have: &ItemStacks,
+        settings: &'a Settings,
+        objective: Objective,
+    ) -> Self {
+        let mut problem = Problem::new();
+        let mut flows: HashMap<Item, Terms> = HashMap::default();
+        let mut recipes = Vec::with_capacity(config.recipes().len());
+        let mut raw = Vec::with_capacity(config.raw().len());
+        let mut power_cost = vec![];
+        let mut building_cost = vec![];
+
+        // Each recipe variable counts machines running at the recipe's clock
+        // speed, so every stack is scaled by the number of cycles one machine
+        // runs per minute
+        for recipe in config.recipes().iter().filter(|r| settings.is_enabled(r)) {
+            let var = problem.add_var();
+            let clock = settings.clock_for(recipe, objective);
+            let rate = recipe.cycles_per_min() * clock.factor();
+            let power = config.machines()[&recipe.machine()].power() * clock.power_factor();
+
+            power_cost.push((var, power.into_ratio()));
+            building_cost.push((var, BigRational::one()));
+
+            for stack in recipe.outputs().stacks_iter() {
+                flows
+                    .entry(stack.0)
+                    .or_default()
+                    .push((var, (stack.1 * &rate).into_ratio()));
+            }
+
+            for stack in recipe.inputs().stacks_iter() {
+                flows
+                    .entry(stack.0)
+                    .or_default()
+                    .push((var, -(stack.1 * &rate).into_ratio()));
+            }
+
+            recipes.push((recipe, clock, var));
+        }
+
+        // Variables and constraints are added in order of item name, since the
+        // solver breaks ties between equally good plans by their order
+        let mut resources: Vec<_> = config.raw().iter().collect();
+        resources.sort_by_cached_key(|(i, _)| i.to_string());
+
+        for (item, resource) in resources {
+            let var = problem.add_var();
+            flows.entry(*item).or_default().push((var, BigRational::one()));
+            raw.push((*item, var));
+
+            if let Some(cap) = resource.cap() {
+                problem.constrain(
+                    [(var, BigRational::one())],
+                    Relation::AtMost,
+                    cap.into_ratio(),
+                );
+            }
+        }
+
+        // Available inputs are free to use, up to the amount available
+        let mut have: Vec<_> = have.stacks_iter().collect();
+        have.sort_by_cached_key(|s| s.0.to_string());
+
+        let supply = have
+            .into_iter()
+            .map(|stack| {
+                let var = problem.add_var();
+                flows.entry(stack.0).or_default().push((var, BigRational::one()));
+                problem.constrain(
+                    [(var, BigRational::one())],
+                    Relation::AtMost,
+                    stack.1.into_ratio(),
+                );
+
+                (stack.0, var)
+            })
+            .collect();
+
+        Self {
+            problem,
+            flows,
+            recipes,
+            raw,
+            supply,
+            targets: vec![],
+            goal: vec![],
+            power_cost,
+            building_cost,
+            settings,
+        }
+    }
+
+    /// Require the net production of every item to cover `want`, exactly for
+    /// items whose excess must be consumed unless they are being maximized
+    pub fn require(&mut self, want: &ItemStacks) {
+        let mut flows: Vec<_> = self.flows.iter().collect();
+        flows.sort_by_cached_key(|(i, _)| i.to_string());
+
+        for (item, coeffs) in flows {
+            let rhs = want.get(item).map_or_else(Zero::zero, |a| a.as_ratio().clone());
+            let rel = match self.settings.byproduct(*item) {
+                ByproductPolicy::Consume if !self.targets.contains(item) => Relation::Equal,
+                ByproductPolicy::Consume | ByproductPolicy::Sink | ByproductPolicy::Surplus => {
+                    Relation::AtLeast
+                },
+            };
+
+            self.problem.constrain(coeffs.iter().cloned(), rel, rhs);
+        }
+    }
+
+    /// Forbid extracting raw resources, and maximize the weighted net
+    /// production of the targets before minimizing any other objective.  Any
+    /// of a target already available doesn't count towards its production.
+    /// Must be called before [`Self::require`], so targets are exempt from the
+    /// consume policy.
+    pub fn maximize(&mut self, targets: &[Target]) {
+        for (_, var) in &self.raw {
+            self.problem
+                .constrain([(*var, BigRational::one())], Relation::AtMost, Zero::zero());
+        }
+
+        for target in targets {
+            let weight = target.weight().as_ratio();
+            let supply = &self.supply;
+
+            self.goal.extend(
+                self.flows
+                    .get(&target.item())
+                    .into_iter()
+                    .flatten()
+                    .filter(|(var, _)| !supply.iter().any(|(_, s)| s == var))
+                    .map(|(var, coeff)| (*var, -(coeff * weight))),
+            );
+            self.targets.push(target.item());
+        }
+    }
+
+    pub fn solve(&self, objective: Objective) -> Result<Vec<BigRational>> {
+        let ones = |vars: &[(Item, usize)]| -> Terms {
+            vars.iter()
+                .map(|(_, var)| (*var, BigRational::one()))
+                .collect()
+        };
+        let raw_cost = ones(&self.raw);
+
+        let [first, second] = match objective {
+            Objective::Raw => [raw_cost, self.power_cost.clone()],
+            Objective::Power => [self.power_cost.clone(), raw_cost],
+            Objective::Buildings => [self.building_cost.clone(), raw_cost],
+        };
+
+        // Leave unneeded inputs unused rather than passing them through
+        let mut objectives = vec![first, second, ones(&self.supply)];
+
+        if !self.goal.is_empty() {
+            objectives.insert(0, self.goal.clone());
+        }
+
+        if self.settings.whole() {
+            let vars: Vec<_> = self.recipes.iter().map(|(_, _, var)| *var).collect();
+
+            self.problem
+                .minimize_lexicographic_integer(&objectives, &vars)
+                .map_err(Into::into)
+        } else {
+            self.problem
+                .minimize_lexicographic(&objectives)
+                .map_err(Into::into)
+        }
+    }
+
+    pub fn into_plan(
+        self,
+        config: &Config,
+        want: &ItemStacks,
+        solution: &[BigRational],
+    ) -> Result<Plan> {
+        let mut recipes = self
+            .recipes
+            .into_iter()
+            .filter(|(_, _, var)| solution[*var].is_positive())
+            .map(|(recipe, clock, var)| {
+                Ok(RecipeRate {
+                    recipe: recipe.clone(),
+                    machines: Amount::new(solution[var].clone())?,
+                    clock: clock.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        recipes.sort_by_cached_key(|r| (r.recipe.machine().to_string(), r.recipe.to_string()));
+
+        let machines = recipes.iter().fold(HashMap::default(), |mut h, r| {
+            *h.entry(r.recipe.machine()).or_insert_with(Amount::zero) += &r.machines;
+            h
+        });
+
+        let power = recipes.iter().fold(HashMap::default(), |mut h, r| {
+            *h.entry(r.recipe.machine()).or_insert_with(Amount::zero) +=
+                r.power(config.machines()[&r.recipe.machine()].power());
+            h
+        });
+
+        let inputs = read_stacks(self.raw, solution)?;
+        let used = read_stacks(self.supply, solution)?;
+
+        let mut extraction: Vec<_> = inputs
+            .stacks_iter()
+            .filter_map(|s| Some((s.0, config.raw()[&s.0].extraction(&s.1)?)))
+            .collect();
+
+        extraction.sort_by_cached_key(|(i, _)| i.to_string());
+
+        let mut outputs = want.clone();
+        let mut surplus = ItemStacks::empty();
+        let mut sunk = ItemStacks::empty();
+
+        for (item, coeffs) in &self.flows {
+            let net = coeffs.iter().map(|(v, c)| c * &solution[*v]).sum::<BigRational>()
+                - want.get(item).map_or_else(Zero::zero, |a| a.as_ratio().clone());
+
+            if !net.is_positive() {
+                continue;
+            }
+
+            let stack = ItemStack(*item, Amount::new(net)?);
+
+            if self.targets.contains(item) {
+                outputs += stack;
+            } else if self.settings.byproduct(*item) == ByproductPolicy::Sink {
+                sunk += stack;
+            } else {
+                surplus += stack;
+            }
+        }
+
+        let mut alternates: Vec<_> = config
+            .recipes()
+            .iter()
+            .filter(|r| r.alternate() && self.settings.is_enabled(r))
+            .cloned()
+            .collect();
+
+        alternates.sort_by_cached_key(ToString::to_string);
+
+        let mut plan = Plan {
+            recipes,
+            machines,
+            power,
+            inputs,
+            used,
+            extraction,
+            outputs,
+            surplus,
+            sunk,
+            edges: vec![],
+            cycles: vec![],
+            alternates,
+        };
+
+        plan.edges = logistics::route(config, self.settings, &plan);
+        plan.cycles = cycles::in_plan(&plan.recipes);
+
+        Ok(plan)
+    }
+}
+
+/// Read the amounts of the given item variables that are used in a solution
+fn read_stacks(vars: Vec<(Item, usize)>, solution: &[BigRational]) -> Result<ItemStacks> {
+    vars.into_iter()
+        .filter(|(_, var)| solution[*var].is_positive())
+        .map(|(item, var)| Ok((item, Amount::new(solution[var].clone())?)))
+        .collect::<Result<_>>()
+        .map(ItemStacks::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::tests::load,
+        items::{Machine, TimeUnit},
+        plan::{simplex::SolveError, ClockTarget},
+    };
+
+    const CONFIG: &str = "
+raw:
+  - crude_oil
+  - iron_ore: { limit: 10 }
+items:
+  crude_oil: { form: liquid }
+  heavy_oil_residue: { form: liquid }
+machines:
+  constructor: { power: 4 }
+  refinery: { power: 30 }
+  smelter: { power: 4 }
+recipes:
+  constructor:
+    - { make: 2 iron_plate, from: 3 iron_ingot, in: 6 }
+  refinery:
+    - { make: 2 plastic 1m3 heavy_oil_residue, from: 3m3 crude_oil, in: 6 }
+  smelter:
+    - { make: 1 iron_ingot, from: 1 iron_ore, in: 2 }
+    - { make: 3 iron_ingot, from: 2 iron_ore, in: 4, name: Pure Iron Ingot, alternate: true }
+";
+
+    fn config() -> Config { load(CONFIG).unwrap() }
+
+    fn amount(s: &str) -> Amount { s.parse().unwrap() }
+
+    fn stacks(items: &[(&str, &str)]) -> ItemStacks {
+        ItemStacks::new(
+            items
+                .iter()
+                .map(|(i, a)| (Item::new(*i).unwrap(), amount(a)))
+                .collect(),
+        )
+    }
+
+    fn calculate(config: &Config, want: &ItemStacks) -> Result<Plan> {
+        Plan::calculate(
+            config,
+            want,
+            &ItemStacks::empty(),
+            &Settings::default(),
+            Objective::Raw,
+        )
+    }
+
+    #[test]
+    fn exact_chain() {
+        let config = config();
+        let plan = calculate(&config, &stacks(&[("iron_plate", "7/2")])).unwrap();
+
+        let machines: Vec<_> = plan
+            .recipes()
+            .iter()
+            .map(|r| (r.recipe().machine().to_string(), r.machines.clone()))
+            .collect();
+
+        assert_eq!(machines, [
+            ("constructor".to_owned(), amount("7/40")),
+            ("smelter".to_owned(), amount("7/40")),
+        ]);
+        assert_eq!(plan.inputs(), &stacks(&[("iron_ore", "21/4")]));
+        assert!(plan.surplus().is_empty());
+    }
+
+    #[test]
+    fn disabled_recipes() {
+        let config = config();
+        let want = stacks(&[("iron_ingot", "6")]);
+        let have = ItemStacks::empty();
+        let pure = "pure_iron_ingot".parse().unwrap();
+
+        let recipes = |settings: &Settings| {
+            let plan = Plan::calculate(&config, &want, &have, settings, Objective::Raw).unwrap();
+
+            plan.recipes()
+                .iter()
+                .map(|r| config.describe(r.recipe()))
+                .collect::<Vec<_>>()
+        };
+
+        // The alternate needs less ore, but is only used once enabled
+        let mut settings = Settings::default();
+        let standard = recipes(&settings);
+        assert_eq!(standard.len(), 1);
+        assert_ne!(standard[0], "Pure Iron Ingot");
+
+        settings.set_enabled(pure, true);
+        assert_eq!(recipes(&settings), ["Pure Iron Ingot"]);
+
+        settings.set_enabled(pure, false);
+        assert_eq!(recipes(&settings), standard);
+    }
+
+    #[test]
+    fn power() {
+        let config = config();
+        let plan = calculate(&config, &stacks(&[("plastic", "40"), ("iron_ingot", "5")])).unwrap();
+        let power = |name: &str| plan.power()[&Machine::new(name).unwrap()].clone();
+
+        let mut machines: Vec<_> = plan.power().keys().collect();
+        let mut expected: Vec<_> = plan.machines().keys().collect();
+        machines.sort_by_cached_key(ToString::to_string);
+        expected.sort_by_cached_key(ToString::to_string);
+        assert_eq!(machines, expected);
+
+        // Two whole refineries at full speed, and one underclocked smelter
+        assert_eq!(power("refinery"), amount("60"));
+        assert!(power("smelter") < amount("4"));
+        assert_eq!(plan.total_power(), power("refinery") + power("smelter"));
+    }
+
+    #[test]
+    fn machine_clock() {
+        let config = config();
+        let mut settings = Settings::default();
+        settings.set_clock("50%".parse().unwrap(), Some("smelter".parse::<ClockTarget>().unwrap()));
+
+        let want = stacks(&[("iron_plate", "7/2")]);
+        let have = ItemStacks::empty();
+        let plan = Plan::calculate(&config, &want, &have, &settings, Objective::Raw).unwrap();
+
+        let machines: Vec<_> = plan
+            .recipes()
+            .iter()
+            .map(|r| (r.recipe().machine().to_string(), r.machines.clone(), r.clock.clone()))
+            .collect();
+
+        assert_eq!(machines, [
+            ("constructor".to_owned(), amount("7/40"), ClockSpeed::full()),
+            ("smelter".to_owned(), amount("7/20"), "50%".parse().unwrap()),
+        ]);
+    }
+
+    #[test]
+    fn maximize_excludes_available_targets() {
+        let config = config();
+        let have = stacks(&[("iron_ore", "30"), ("iron_ingot", "30")]);
+        let targets = ["iron_ingot".parse().unwrap()];
+        let plan = Plan::maximize(
+            &config,
+            &targets,
+            &ItemStacks::empty(),
+            &have,
+            &Settings::default(),
+            Objective::Raw,
+        )
+        .unwrap();
+
+        assert_eq!(plan.outputs(), &stacks(&[("iron_ingot", "30")]));
+        assert_eq!(plan.used(), &stacks(&[("iron_ore", "30")]));
+    }
+
+    #[test]
+    fn maximize_overrides_consume() {
+        let config = config();
+        let ingot = Item::new("iron_ingot").unwrap();
+        let mut settings = Settings::default();
+        settings.set_byproduct(ingot, ByproductPolicy::Consume);
+
+        let have = stacks(&[("iron_ore", "30")]);
+        let targets = [Target::new(ingot, Amount::one())];
+        let want = ItemStacks::empty();
+        let plan = Plan::maximize(&config, &targets, &want, &have, &settings, Objective::Raw);
+
+        assert_eq!(plan.unwrap().outputs(), &stacks(&[("iron_ingot", "30")]));
+    }
+
+    #[test]
+    fn byproducts() {
+        let config = config();
+        let plastic = Item::new("plastic").unwrap();
+        let plan = calculate(&config, &stacks(&[("plastic", "30")])).unwrap();
+
+        let [rate] = plan.recipes() else {
+            panic!("Expected one recipe, got {:?}", plan.recipes());
+        };
+        let keyed = &config.keyed_recipes()[&plastic][0];
+        let cycles = rate.cycles_per_min();
+
+        let expected = ItemStacks::new(
+            keyed
+                .extra_outs()
+                .iter()
+                .map(|(i, a)| (*i, a * &cycles))
+                .collect(),
+        );
+
+        assert_eq!(plan.surplus(), &expected);
+        assert_eq!(plan.surplus(), &stacks(&[("heavy_oil_residue", "15")]));
+        assert_eq!(plan.inputs(), &stacks(&[("crude_oil", "45")]));
+    }
+
+    #[test]
+    fn tied_optimum() {
+        // Either ore makes an ingot for the same cost, so only the order of the
+        // solver's variables decides between them
+        const TIED: &str = "
+raw: [copper_ore, iron_ore]
+machines:
+  smelter: { power: 4 }
+recipes:
+  smelter:
+    - { make: 1 ingot, from: 1 copper_ore, in: 2, name: Copper Ingot }
+    - { make: 1 ingot, from: 1 iron_ore, in: 2, name: Iron Ingot }
+";
+
+        let plans: Vec<_> = (0..8)
+            .map(|_| {
+                let config = load(TIED).unwrap();
+                let plan = calculate(&config, &stacks(&[("ingot", "30")])).unwrap();
+
+                plan.display(&config, TimeUnit::Minute).to_string()
+            })
+            .collect();
+
+        assert!(plans.iter().all(|p| *p == plans[0]), "{:#?}", plans);
+    }
+
+    #[test]
+    fn infeasible() {
+        let config = config();
+        let err = calculate(&config, &stacks(&[("iron_plate", "7")])).unwrap_err();
+
+        assert!(matches!(
+            err.root_cause().downcast_ref::<SolveError>(),
+            Some(SolveError::Infeasible)
+        ));
+    }
+}
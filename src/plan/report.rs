@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use num_traits::Signed;
+use serde::{Deserialize, Serialize};
+
+use super::{Node, Plan};
+use crate::{
+    config::Config,
+    items::{Amount, ItemStacks, TimeUnit},
+};
+
+/// The version of the structure of serialized plans.  It is increased whenever
+/// a field is removed or changes meaning, but not when one is added.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A calculated plan in a form suited to serializing for other programs.
+/// Rates are given per `unit`, with fluids in cubic metres, and items,
+/// machines and recipes are named by their config ids.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    /// Always [`SCHEMA_VERSION`]
+    schema_version: u32,
+    /// The unit of time rates are given per, one of `s`, `min` or `h`
+    unit: String,
+    recipes: Vec<RecipeReport>,
+    /// The number of each machine running, at the recipes' planned clock speeds
+    machines: BTreeMap<String, Amount>,
+    power: PowerReport,
+    /// Raw resources extracted
+    inputs: BTreeMap<String, Amount>,
+    /// Resource nodes mined for each raw resource with known nodes
+    extraction: BTreeMap<String, ExtractionReport>,
+    /// Available inputs used
+    used: BTreeMap<String, Amount>,
+    /// Wanted outputs and maximized targets
+    outputs: BTreeMap<String, Amount>,
+    surplus: BTreeMap<String, Amount>,
+    sunk: BTreeMap<String, Amount>,
+    /// Every flow of an item between two parts of the plan
+    logistics: Vec<EdgeReport>,
+    /// Every loop among the recipes used
+    cycles: Vec<CycleReport>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeReport {
+    /// The recipe's id, or none for an unnamed recipe
+    id: Option<String>,
+    /// The recipe's display name, or a summary of its inputs and outputs
+    name: String,
+    machine: String,
+    /// The exact number of machines needed at `clock`
+    machines: Amount,
+    /// The planned clock speed, as a percentage
+    clock: Amount,
+    /// The number of machines to build, all underclocked to `built_clock`
+    built: Amount,
+    built_clock: Amount,
+    /// The number of times the recipe completes
+    cycles: Amount,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerReport {
+    /// The power drawn by each type of machine, in MW
+    machines: BTreeMap<String, Amount>,
+    total: Amount,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionReport {
+    extractor: String,
+    /// The number of nodes of each purity mined
+    nodes: BTreeMap<String, u32>,
+}
+
+/// One end of a flow of items
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NodeReport {
+    Raw,
+    Available,
+    /// A recipe, given by its index in `recipes`
+    Recipe {
+        index: usize,
+    },
+    Output,
+    Surplus,
+    Sink,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeReport {
+    item: String,
+    from: NodeReport,
+    to: NodeReport,
+    rate: Amount,
+    /// Either `belt` or `pipe`
+    conveyor: String,
+    /// The slowest unlocked tier carrying the whole rate, or else the fastest
+    tier: String,
+    /// The number of lines of `tier` needed
+    lines: Amount,
+    /// Whether the rate exceeds one line of the highest unlocked tier
+    over: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CycleReport {
+    /// The recipes in the loop, by their index in `recipes`
+    recipes: Vec<usize>,
+    /// The items the loop makes more of than it uses, at their net rates
+    made: BTreeMap<String, Amount>,
+    /// The items the loop uses more of than it makes, at their net rates
+    used: BTreeMap<String, Amount>,
+    /// The items the loop makes exactly as much of as it uses
+    balanced: Vec<String>,
+}
+
+fn rates(stacks: &ItemStacks, unit: TimeUnit) -> BTreeMap<String, Amount> {
+    stacks
+        .iter()
+        .map(|(item, amt)| (item.to_string(), unit.convert(amt)))
+        .collect()
+}
+
+fn percent(factor: &Amount) -> Amount { factor * Amount::from_integer(100) }
+
+impl From<Node> for NodeReport {
+    fn from(node: Node) -> Self {
+        match node {
+            Node::Raw(_) => Self::Raw,
+            Node::Available(_) => Self::Available,
+            Node::Recipe(index) => Self::Recipe { index },
+            Node::Output(_) => Self::Output,
+            Node::Surplus(_) => Self::Surplus,
+            Node::Sink(_) => Self::Sink,
+        }
+    }
+}
+
+impl Report {
+    pub fn new(plan: &Plan, config: &Config, unit: TimeUnit) -> Self {
+        let recipes = plan
+            .recipes
+            .iter()
+            .map(|rate| {
+                let (built, built_clock) = rate.build();
+
+                RecipeReport {
+                    id: rate.recipe.id().map(|i| i.to_string()),
+                    name: config.describe(&rate.recipe),
+                    machine: rate.recipe.machine().to_string(),
+                    machines: rate.machines.clone(),
+                    clock: percent(rate.clock.factor()),
+                    built,
+                    built_clock: percent(built_clock.factor()),
+                    cycles: unit.convert(&rate.cycles_per_min()),
+                }
+            })
+            .collect();
+
+        let extraction = plan
+            .extraction
+            .iter()
+            .map(|(item, e)| {
+                let nodes = e.nodes().iter().map(|(p, n)| (p.to_string(), *n)).collect();
+
+                (item.to_string(), ExtractionReport {
+                    extractor: e.extractor().to_string(),
+                    nodes,
+                })
+            })
+            .collect();
+
+        let logistics = plan
+            .edges
+            .iter()
+            .map(|edge| EdgeReport {
+                item: edge.item().to_string(),
+                from: edge.from().into(),
+                to: edge.to().into(),
+                rate: unit.convert(edge.rate()),
+                conveyor: edge.lines().conveyor().to_string(),
+                tier: edge.lines().tier().into(),
+                lines: edge.lines().count().clone(),
+                over: edge.lines().is_over(),
+            })
+            .collect();
+
+        let cycles = plan
+            .cycles
+            .iter()
+            .map(|cycle| {
+                let mut report = CycleReport {
+                    recipes: cycle.recipes().to_vec(),
+                    made: BTreeMap::new(),
+                    used: BTreeMap::new(),
+                    balanced: vec![],
+                };
+
+                for (item, net) in cycle.items() {
+                    let rate = || {
+                        unit.convert(
+                            &Amount::new(net.abs()).expect("Absolute values are non-negative"),
+                        )
+                    };
+
+                    if net.is_positive() {
+                        report.made.insert(item.to_string(), rate());
+                    } else if net.is_negative() {
+                        report.used.insert(item.to_string(), rate());
+                    } else {
+                        report.balanced.push(item.to_string());
+                    }
+                }
+
+                report
+            })
+            .collect();
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            unit: unit.to_string(),
+            recipes,
+            machines: plan
+                .machines
+                .iter()
+                .map(|(m, n)| (m.to_string(), n.clone()))
+                .collect(),
+            power: PowerReport {
+                machines: plan
+                    .power
+                    .iter()
+                    .map(|(m, mw)| (m.to_string(), mw.clone()))
+                    .collect(),
+                total: plan.total_power(),
+            },
+            inputs: rates(&plan.inputs, unit),
+            extraction,
+            used: rates(&plan.used, unit),
+            outputs: rates(&plan.outputs, unit),
+            surplus: rates(&plan.surplus, unit),
+            sunk: rates(&plan.sunk, unit),
+            logistics,
+            cycles,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::tests::load,
+        items::Item,
+        plan::{Objective, Settings},
+    };
+
+    const CONFIG: &str = "
+raw:
+  - crude_oil
+  - iron_ore: { nodes: { normal: 2 }, extractors: { miner: 60 } }
+items:
+  crude_oil: { form: liquid }
+  heavy_oil_residue: { form: liquid }
+machines:
+  constructor: { power: 4 }
+  miner: { power: 5 }
+  refinery: { power: 30 }
+  smelter: { power: 4 }
+recipes:
+  constructor:
+    - { make: 2 iron_plate, from: 3 iron_ingot, in: 6 }
+  refinery:
+    - { make: 2 plastic 1m3 heavy_oil_residue, from: 3m3 crude_oil, in: 6 }
+  smelter:
+    - { make: 1 iron_ingot, from: 1 iron_ore, in: 2 }
+";
+
+    #[test]
+    fn round_trip() {
+        let config = load(CONFIG).unwrap();
+        let want = ItemStacks::new(
+            [("iron_plate", "20"), ("plastic", "10")]
+                .into_iter()
+                .map(|(i, a)| (Item::new(i).unwrap(), a.parse().unwrap()))
+                .collect(),
+        );
+        let have = ItemStacks::empty();
+        let settings = Settings::default();
+        let plan = Plan::calculate(&config, &want, &have, &settings, Objective::Raw).unwrap();
+        let report = plan.report(&config, TimeUnit::Hour);
+
+        assert_eq!(report.schema_version, SCHEMA_VERSION);
+        assert_eq!(report.unit, "h");
+        assert_eq!(report.power.total, plan.total_power());
+        assert_eq!(report.outputs["iron_plate"], Amount::from_integer(1200));
+        assert_eq!(report.extraction["iron_ore"].nodes["normal"], 1);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report, "{}", json);
+
+        let yaml = serde_yaml::to_string(&report).unwrap();
+        assert_eq!(serde_yaml::from_str::<Report>(&yaml).unwrap(), report, "{}", yaml);
+    }
+}
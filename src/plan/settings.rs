@@ -0,0 +1,247 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde::{de, ser, Deserialize, Serialize};
+use thiserror::Error;
+
+use super::ClockSpeed;
+use crate::{
+    config::{Conveyor, Recipe},
+    items::{Item, Machine, RecipeName},
+    HashMap,
+};
+
+#[derive(Debug, Error)]
+#[error("Unrecognized objective {0:?}")]
+pub struct InvalidObjective(String);
+
+#[derive(Debug, Error)]
+#[error("Unrecognized byproduct policy {0:?}")]
+pub struct InvalidPolicy(String);
+
+#[derive(Debug, Error)]
+#[error("No machine or recipe is named {0:?}")]
+pub struct InvalidClockTarget(String);
+
+/// The quantity a plan is chosen to minimize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Objective {
+    /// The total rate of raw resources consumed
+    #[default]
+    Raw,
+    /// The total power drawn by all machines
+    Power,
+    /// The total number of machines, overclocking them as far as allowed
+    Buildings,
+}
+
+/// What a plan may do with any of an item produced beyond what is wanted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ByproductPolicy {
+    /// The excess must be used up by other recipes in the plan
+    Consume,
+    /// The excess is destroyed in an AWESOME Sink
+    Sink,
+    /// The excess is left over for use elsewhere
+    #[default]
+    Surplus,
+}
+
+/// The machines a clock speed applies to, when not all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClockTarget {
+    /// Every machine of one type
+    Machine(Machine),
+    /// Every machine running one recipe
+    Recipe(RecipeName),
+}
+
+/// Session options controlling which plans the solver may produce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    #[serde(serialize_with = "sorted")]
+    recipes: HashMap<RecipeName, bool>,
+    clock: ClockSpeed,
+    #[serde(serialize_with = "sorted")]
+    recipe_clocks: HashMap<RecipeName, ClockSpeed>,
+    #[serde(serialize_with = "sorted")]
+    machine_clocks: HashMap<Machine, ClockSpeed>,
+    max_clock: ClockSpeed,
+    whole: bool,
+    #[serde(serialize_with = "sorted")]
+    byproducts: HashMap<Item, ByproductPolicy>,
+    #[serde(serialize_with = "sorted")]
+    max_tiers: HashMap<Conveyor, String>,
+}
+
+/// Serialize a map sorted by key, so saved settings don't change order
+/// between runs
+fn sorted<K: fmt::Display, V: Serialize, S: ser::Serializer>(
+    map: &HashMap<K, V>,
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    let map: BTreeMap<_, _> = map.iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+    map.serialize(ser)
+}
+
+impl Settings {
+    /// Whether the solver may use a recipe.  Unless overridden, alternate
+    /// recipes start disabled and all others start enabled.
+    pub fn is_enabled(&self, recipe: &Recipe) -> bool {
+        recipe
+            .id()
+            .and_then(|id| self.recipes.get(&id).copied())
+            .unwrap_or(!recipe.alternate())
+    }
+
+    pub fn set_enabled(&mut self, id: RecipeName, enabled: bool) {
+        self.recipes.insert(id, enabled);
+    }
+
+    /// The clock speed machines running a recipe are planned at.  Recipes
+    /// without their own clock speed run at their machine's speed, and failing
+    /// that at the global speed, or as fast as allowed when minimizing
+    /// buildings.
+    pub fn clock_for(&self, recipe: &Recipe, objective: Objective) -> &ClockSpeed {
+        recipe
+            .id()
+            .and_then(|id| self.recipe_clocks.get(&id))
+            .or_else(|| self.machine_clocks.get(&recipe.machine()))
+            .unwrap_or(match objective {
+                Objective::Buildings => &self.max_clock,
+                Objective::Raw | Objective::Power => &self.clock,
+            })
+    }
+
+    /// Set the clock speed of one machine or recipe, or of everything without
+    /// its own
+    pub fn set_clock(&mut self, clock: ClockSpeed, target: Option<ClockTarget>) {
+        match target {
+            Some(ClockTarget::Machine(machine)) => {
+                self.machine_clocks.insert(machine, clock);
+            },
+            Some(ClockTarget::Recipe(id)) => {
+                self.recipe_clocks.insert(id, clock);
+            },
+            None => self.clock = clock,
+        }
+    }
+
+    pub fn set_max_clock(&mut self, clock: ClockSpeed) { self.max_clock = clock; }
+
+    /// Whether plans are solved with a whole number of machines per recipe
+    pub fn whole(&self) -> bool { self.whole }
+
+    pub fn set_whole(&mut self, whole: bool) { self.whole = whole; }
+
+    /// What a plan may do with excess of an item, by default leaving it as
+    /// surplus
+    pub fn byproduct(&self, item: Item) -> ByproductPolicy {
+        self.byproducts.get(&item).copied().unwrap_or_default()
+    }
+
+    pub fn set_byproduct(&mut self, item: Item, policy: ByproductPolicy) {
+        self.byproducts.insert(item, policy);
+    }
+
+    /// Every item with a byproduct policy set explicitly
+    pub fn byproducts(&self) -> &HashMap<Item, ByproductPolicy> { &self.byproducts }
+
+    /// The name of the highest unlocked tier of a conveyor, if not all tiers
+    /// are unlocked
+    pub fn max_tier(&self, conveyor: Conveyor) -> Option<&str> {
+        self.max_tiers.get(&conveyor).map(String::as_str)
+    }
+
+    pub fn set_max_tier(&mut self, conveyor: Conveyor, tier: String) {
+        self.max_tiers.insert(conveyor, tier);
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            recipes: HashMap::default(),
+            clock: ClockSpeed::full(),
+            recipe_clocks: HashMap::default(),
+            machine_clocks: HashMap::default(),
+            max_clock: ClockSpeed::max(),
+            whole: false,
+            byproducts: HashMap::default(),
+            max_tiers: HashMap::default(),
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Raw => "raw",
+            Self::Power => "power",
+            Self::Buildings => "buildings",
+        })
+    }
+}
+
+impl FromStr for Objective {
+    type Err = InvalidObjective;
+
+    fn from_str(s: &str) -> Result<Self, InvalidObjective> {
+        Ok(match s {
+            "raw" | "resources" => Self::Raw,
+            "power" => Self::Power,
+            "buildings" | "machines" => Self::Buildings,
+            s => return Err(InvalidObjective(s.to_owned())),
+        })
+    }
+}
+
+impl FromStr for ClockTarget {
+    type Err = InvalidClockTarget;
+
+    /// Parses a machine name, or failing that a recipe name
+    fn from_str(s: &str) -> Result<Self, InvalidClockTarget> {
+        Machine::new(s)
+            .map(Self::Machine)
+            .or_else(|_| RecipeName::new(s).map(Self::Recipe))
+            .map_err(|_| InvalidClockTarget(s.to_owned()))
+    }
+}
+
+impl fmt::Display for ByproductPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Consume => "consume",
+            Self::Sink => "sink",
+            Self::Surplus => "surplus",
+        })
+    }
+}
+
+impl FromStr for ByproductPolicy {
+    type Err = InvalidPolicy;
+
+    fn from_str(s: &str) -> Result<Self, InvalidPolicy> {
+        Ok(match s {
+            "consume" | "recycle" => Self::Consume,
+            "sink" => Self::Sink,
+            "surplus" | "keep" => Self::Surplus,
+            s => return Err(InvalidPolicy(s.to_owned())),
+        })
+    }
+}
+
+impl ser::Serialize for ByproductPolicy {
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.collect_str(self)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ByproductPolicy {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let s: String = de::Deserialize::deserialize(de)?;
+
+        s.parse().map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,514 @@
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use thiserror::Error;
+
+/// The most subproblems a branch-and-bound search will solve before giving up
+const MAX_BRANCHES: usize = 2000;
+
+#[derive(Debug, Error)]
+pub enum SolveError {
+    #[error("No solution satisfies all constraints")]
+    Infeasible,
+    #[error("The objective is unbounded")]
+    Unbounded,
+    #[error("Gave up searching for a whole-number solution after {0} subproblems")]
+    TooManyBranches(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    AtLeast,
+    AtMost,
+    Equal,
+}
+
+impl Relation {
+    fn flip(self) -> Self {
+        match self {
+            Self::AtLeast => Self::AtMost,
+            Self::AtMost => Self::AtLeast,
+            Self::Equal => Self::Equal,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    coeffs: Vec<(usize, BigRational)>,
+    rel: Relation,
+    rhs: BigRational,
+}
+
+/// A linear program over non-negative variables, minimized exactly with a
+/// dense two-phase simplex method using Bland's rule
+#[derive(Debug, Clone, Default)]
+pub struct Problem {
+    vars: usize,
+    constraints: Vec<Constraint>,
+}
+
+impl Problem {
+    pub fn new() -> Self { Self::default() }
+
+    /// Add a non-negative variable, returning its index
+    pub fn add_var(&mut self) -> usize {
+        self.vars += 1;
+        self.vars - 1
+    }
+
+    pub fn constrain(
+        &mut self,
+        coeffs: impl IntoIterator<Item = (usize, BigRational)>,
+        rel: Relation,
+        rhs: BigRational,
+    ) {
+        let coeffs: Vec<_> = coeffs.into_iter().filter(|(_, c)| !c.is_zero()).collect();
+
+        debug_assert!(coeffs.iter().all(|(v, _)| *v < self.vars));
+
+        self.constraints.push(Constraint { coeffs, rel, rhs });
+    }
+
+    /// Find a value for each variable minimizing the sum of the given terms
+    pub fn minimize(
+        &self,
+        objective: impl IntoIterator<Item = (usize, BigRational)>,
+    ) -> Result<Vec<BigRational>, SolveError> {
+        let mut costs = vec![BigRational::zero(); self.vars];
+
+        for (var, coeff) in objective {
+            costs[var] += coeff;
+        }
+
+        Tableau::new(self).solve(costs)
+    }
+
+    /// Minimize each objective in turn, holding every earlier objective at its
+    /// optimal value
+    pub fn minimize_lexicographic(
+        &self,
+        objectives: &[Vec<(usize, BigRational)>],
+    ) -> Result<Vec<BigRational>, SolveError> {
+        let mut problem = self.clone();
+        let mut solution = None;
+
+        for objective in objectives {
+            let x = problem.minimize(objective.iter().cloned())?;
+            let best = objective.iter().map(|(v, c)| c * &x[*v]).sum();
+
+            problem.constrain(objective.iter().cloned(), Relation::AtMost, best);
+            solution = Some(x);
+        }
+
+        solution.map_or_else(|| problem.minimize(None), Ok)
+    }
+
+    /// Minimize the objectives lexicographically while requiring each of the
+    /// variables in `integers` to take a whole value, using a depth-first
+    /// branch-and-bound search over the relaxed problem
+    pub fn minimize_lexicographic_integer(
+        &self,
+        objectives: &[Vec<(usize, BigRational)>],
+        integers: &[usize],
+    ) -> Result<Vec<BigRational>, SolveError> {
+        self.branch_and_bound(objectives, integers, MAX_BRANCHES)
+    }
+
+    fn branch_and_bound(
+        &self,
+        objectives: &[Vec<(usize, BigRational)>],
+        integers: &[usize],
+        max_branches: usize,
+    ) -> Result<Vec<BigRational>, SolveError> {
+        let value = |x: &[BigRational]| -> Vec<BigRational> {
+            objectives
+                .iter()
+                .map(|o| o.iter().map(|(v, c)| c * &x[*v]).sum())
+                .collect()
+        };
+
+        let mut stack = vec![self.clone()];
+        let mut best: Option<(Vec<BigRational>, Vec<BigRational>)> = None;
+        let mut branches = 0;
+
+        while let Some(problem) = stack.pop() {
+            branches += 1;
+
+            if branches > max_branches {
+                return Err(SolveError::TooManyBranches(max_branches));
+            }
+
+            let x = match problem.minimize_lexicographic(objectives) {
+                Ok(x) => x,
+                Err(SolveError::Infeasible) => continue,
+                Err(e) => return Err(e),
+            };
+
+            // The relaxed optimum bounds every whole solution below it
+            let val = value(&x);
+
+            if best.iter().any(|(b, _)| val >= *b) {
+                continue;
+            }
+
+            match integers.iter().copied().find(|v| !x[*v].is_integer()) {
+                Some(var) => {
+                    let mut down = problem.clone();
+                    down.constrain([(var, BigRational::one())], Relation::AtMost, x[var].floor());
+
+                    let mut up = problem;
+                    up.constrain([(var, BigRational::one())], Relation::AtLeast, x[var].ceil());
+
+                    // Rounding up usually stays feasible, so try it first
+                    stack.push(down);
+                    stack.push(up);
+                },
+                None => best = Some((val, x)),
+            }
+        }
+
+        best.map(|(_, x)| x).ok_or(SolveError::Infeasible)
+    }
+}
+
+struct Tableau {
+    rows: Vec<Vec<BigRational>>,
+    basis: Vec<usize>,
+    vars: usize,
+    artificial: usize,
+}
+
+impl Tableau {
+    fn new(problem: &Problem) -> Self {
+        let vars = problem.vars;
+        let constraints: Vec<_> = problem
+            .constraints
+            .iter()
+            .map(|c| {
+                if c.rhs.is_negative() {
+                    Constraint {
+                        coeffs: c.coeffs.iter().map(|(v, c)| (*v, -c)).collect(),
+                        rel: c.rel.flip(),
+                        rhs: -&c.rhs,
+                    }
+                } else {
+                    c.clone()
+                }
+            })
+            .collect();
+
+        let slacks = constraints
+            .iter()
+            .filter(|c| c.rel != Relation::Equal)
+            .count();
+        let artificials = constraints
+            .iter()
+            .filter(|c| c.rel != Relation::AtMost)
+            .count();
+        let artificial = vars + slacks;
+        let width = artificial + artificials + 1;
+
+        let mut rows = Vec::with_capacity(constraints.len());
+        let mut basis = Vec::with_capacity(constraints.len());
+        let mut next_slack = vars;
+        let mut next_artificial = artificial;
+
+        for Constraint { coeffs, rel, rhs } in constraints {
+            let mut row = vec![BigRational::zero(); width];
+
+            for (var, coeff) in coeffs {
+                row[var] += coeff;
+            }
+
+            row[width - 1] = rhs;
+
+            match rel {
+                Relation::AtMost => {
+                    row[next_slack] = BigRational::one();
+                    basis.push(next_slack);
+                    next_slack += 1;
+                },
+                Relation::AtLeast => {
+                    row[next_slack] = -BigRational::one();
+                    next_slack += 1;
+                    row[next_artificial] = BigRational::one();
+                    basis.push(next_artificial);
+                    next_artificial += 1;
+                },
+                Relation::Equal => {
+                    row[next_artificial] = BigRational::one();
+                    basis.push(next_artificial);
+                    next_artificial += 1;
+                },
+            }
+
+            rows.push(row);
+        }
+
+        Self {
+            rows,
+            basis,
+            vars,
+            artificial,
+        }
+    }
+
+    fn width(&self) -> usize { self.rows.first().map_or(0, Vec::len) }
+
+    fn solve(mut self, mut costs: Vec<BigRational>) -> Result<Vec<BigRational>, SolveError> {
+        let width = self.width();
+
+        if width > 0 {
+            let phase1: Vec<_> = (0..width - 1)
+                .map(|j| {
+                    if j >= self.artificial {
+                        BigRational::one()
+                    } else {
+                        BigRational::zero()
+                    }
+                })
+                .collect();
+
+            self.run(&phase1, width - 1)?;
+
+            if self.objective(&phase1).is_positive() {
+                return Err(SolveError::Infeasible);
+            }
+
+            self.evict_artificials();
+
+            costs.resize(width - 1, BigRational::zero());
+
+            self.run(&costs, self.artificial)?;
+        } else if costs.iter().any(Signed::is_negative) {
+            // With no constraints, any variable with a negative cost can grow
+            // without limit
+            return Err(SolveError::Unbounded);
+        }
+
+        let mut ret = vec![BigRational::zero(); self.vars];
+
+        for (row, var) in self.rows.iter().zip(&self.basis) {
+            if *var < self.vars {
+                ret[*var] = row[row.len() - 1].clone();
+            }
+        }
+
+        Ok(ret)
+    }
+
+    fn objective(&self, costs: &[BigRational]) -> BigRational {
+        self.rows
+            .iter()
+            .zip(&self.basis)
+            .map(|(row, var)| &costs[*var] * &row[row.len() - 1])
+            .sum()
+    }
+
+    /// Pivot until no column below `allowed` has a negative reduced cost
+    fn run(&mut self, costs: &[BigRational], allowed: usize) -> Result<(), SolveError> {
+        loop {
+            let entering = (0..allowed).find(|j| {
+                let reduced = &costs[*j]
+                    - self
+                        .rows
+                        .iter()
+                        .zip(&self.basis)
+                        .map(|(row, var)| &costs[*var] * &row[*j])
+                        .sum::<BigRational>();
+
+                reduced.is_negative()
+            });
+
+            let entering = match entering {
+                Some(j) => j,
+                None => break Ok(()),
+            };
+
+            let leaving = self
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| row[entering].is_positive())
+                .map(|(i, row)| (i, &row[row.len() - 1] / &row[entering]))
+                .min_by(|(i, a), (j, b)| {
+                    a.cmp(b)
+                        .then_with(|| self.basis[*i].cmp(&self.basis[*j]))
+                })
+                .map(|(i, _)| i)
+                .ok_or(SolveError::Unbounded)?;
+
+            self.pivot(leaving, entering);
+        }
+    }
+
+    /// Replace any artificial variables left in the basis after phase one
+    fn evict_artificials(&mut self) {
+        for i in 0..self.rows.len() {
+            if self.basis[i] < self.artificial {
+                continue;
+            }
+
+            if let Some(j) = (0..self.artificial).find(|j| !self.rows[i][*j].is_zero()) {
+                self.pivot(i, j);
+            }
+        }
+    }
+
+    fn pivot(&mut self, row: usize, col: usize) {
+        let scale = self.rows[row][col].clone();
+        self.rows[row].iter_mut().for_each(|v| *v /= &scale);
+
+        let pivot = self.rows[row].clone();
+
+        for (i, other) in self.rows.iter_mut().enumerate() {
+            let factor = other[col].clone();
+
+            if i == row || factor.is_zero() {
+                continue;
+            }
+
+            other
+                .iter_mut()
+                .zip(&pivot)
+                .for_each(|(v, p)| *v -= &factor * p);
+        }
+
+        self.basis[row] = col;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(num: i64, den: i64) -> BigRational { BigRational::new(num.into(), den.into()) }
+
+    #[test]
+    fn exact_optimum() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+        let y = problem.add_var();
+
+        problem.constrain([(x, r(2, 1)), (y, r(1, 1))], Relation::AtLeast, r(1, 1));
+        problem.constrain([(x, r(1, 1)), (y, r(3, 1))], Relation::AtLeast, r(1, 1));
+
+        let sol = problem.minimize([(x, r(1, 1)), (y, r(1, 1))]).unwrap();
+
+        assert_eq!(sol, [r(2, 5), r(1, 5)]);
+    }
+
+    #[test]
+    fn lexicographic_tiebreak() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+        let y = problem.add_var();
+
+        problem.constrain([(x, r(1, 1)), (y, r(1, 1))], Relation::Equal, r(3, 1));
+
+        // Both variables cost the same at first, so the second objective
+        // decides between them
+        let sol = problem
+            .minimize_lexicographic(&[vec![(x, r(1, 1)), (y, r(1, 1))], vec![(x, r(1, 1))]])
+            .unwrap();
+
+        assert_eq!(sol, [r(0, 1), r(3, 1)]);
+    }
+
+    #[test]
+    fn infeasible() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+
+        problem.constrain([(x, r(1, 1))], Relation::AtMost, r(1, 1));
+        problem.constrain([(x, r(1, 1))], Relation::AtLeast, r(2, 1));
+
+        assert!(matches!(problem.minimize([(x, r(1, 1))]), Err(SolveError::Infeasible)));
+    }
+
+    #[test]
+    fn integer_optimum() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+        let y = problem.add_var();
+
+        problem.constrain([(x, r(-1, 1)), (y, r(1, 1))], Relation::AtMost, r(1, 1));
+        problem.constrain([(x, r(3, 1)), (y, r(2, 1))], Relation::AtMost, r(12, 1));
+        problem.constrain([(x, r(2, 1)), (y, r(3, 1))], Relation::AtMost, r(12, 1));
+
+        // Maximize y, then prefer the smallest x
+        let objectives = [vec![(y, r(-1, 1))], vec![(x, r(1, 1))]];
+
+        let relaxed = problem.minimize_lexicographic(&objectives).unwrap();
+        assert_eq!(relaxed, [r(9, 5), r(14, 5)]);
+
+        let whole = problem.minimize_lexicographic_integer(&objectives, &[x, y]).unwrap();
+        assert_eq!(whole, [r(1, 1), r(2, 1)]);
+    }
+
+    #[test]
+    fn integer_infeasible() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+
+        // 2x = 3 has a fractional solution but no whole one
+        problem.constrain([(x, r(2, 1))], Relation::Equal, r(3, 1));
+
+        assert!(matches!(
+            problem.minimize_lexicographic_integer(&[vec![(x, r(1, 1))]], &[x]),
+            Err(SolveError::Infeasible)
+        ));
+    }
+
+    #[test]
+    fn too_many_branches() {
+        let mut problem = Problem::new();
+        let vars: Vec<_> = (0..7).map(|_| problem.add_var()).collect();
+
+        // Each variable is 0 or 1 and their sum is 7/2, which the relaxation
+        // can always satisfy but no whole solution can, so the search has to
+        // fix about half of the variables on every path before giving up
+        for &v in &vars {
+            problem.constrain([(v, r(1, 1))], Relation::AtMost, r(1, 1));
+        }
+
+        problem.constrain(vars.iter().map(|v| (*v, r(2, 1))), Relation::Equal, r(7, 1));
+
+        let objectives = [vars.iter().map(|v| (*v, r(1, 1))).collect()];
+
+        assert!(matches!(
+            problem.branch_and_bound(&objectives, &vars, 1000),
+            Err(SolveError::Infeasible)
+        ));
+        let err = problem.branch_and_bound(&objectives, &vars, 10).unwrap_err();
+
+        assert!(matches!(err, SolveError::TooManyBranches(10)));
+        assert_eq!(
+            err.to_string(),
+            "Gave up searching for a whole-number solution after 10 subproblems"
+        );
+    }
+
+    #[test]
+    fn unbounded() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+
+        problem.constrain([(x, r(1, 1))], Relation::AtLeast, r(1, 1));
+
+        assert!(matches!(problem.minimize([(x, r(-1, 1))]), Err(SolveError::Unbounded)));
+    }
+
+    #[test]
+    fn unconstrained() {
+        let mut problem = Problem::new();
+        let x = problem.add_var();
+        let y = problem.add_var();
+
+        assert_eq!(problem.minimize([(x, r(1, 1))]).unwrap(), [r(0, 1), r(0, 1)]);
+        assert!(matches!(
+            problem.minimize([(x, r(1, 1)), (y, r(-1, 1))]),
+            Err(SolveError::Unbounded)
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fmt, str::FromStr};
+
+use num_traits::One;
+use thiserror::Error;
+
+use crate::items::{Amount, InvalidItem, Item, ParseError};
+
+#[derive(Debug, Error)]
+pub enum TargetError {
+    #[error("{0}")]
+    Item(#[from] InvalidItem),
+    #[error("Invalid weight: {0}")]
+    Weight(#[from] ParseError),
+}
+
+/// An item whose output should be maximized, weighted against any others
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Target {
+    item: Item,
+    weight: Amount,
+}
+
+impl Target {
+    pub fn new(item: Item, weight: Amount) -> Self { Self { item, weight } }
+
+    pub fn item(&self) -> Item { self.item }
+
+    pub fn weight(&self) -> &Amount { &self.weight }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.weight.is_one() {
+            write!(f, "{}", self.item)
+        } else {
+            write!(f, "{}:{}", self.item, self.weight)
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = TargetError;
+
+    /// Parses an item name with an optional weight, such as `"screw:2"`
+    fn from_str(s: &str) -> Result<Self, TargetError> {
+        let (item, weight) = match s.split_once(':') {
+            Some((item, weight)) => (item, weight.parse()?),
+            None => (s, Amount::one()),
+        };
+
+        Ok(Self::new(item.trim().parse()?, weight))
+    }
+}
@@ -1,35 +1,220 @@
-use std::{fmt, fmt::Write};
+use std::{borrow::Cow, fmt, fmt::Write, path::PathBuf};
 
 use crate::{
-    items::{Amount, Item},
+    config::Conveyor,
+    export::Format,
+    items::{Item, Rate, RecipeName, TimeUnit},
+    plan::{ByproductPolicy, ClockSpeed, ClockTarget, Objective, Target},
     prelude::*,
 };
 
 #[derive(Docbot, Debug)]
 pub enum BaseCommand {
-    /// `(want|add) <item> [amount]`
+    /// `(want|add) <item> [rate]`
     /// Add an item to the selected outputs
     ///
     /// # Arguments
     /// item: The name of the item to add
-    /// amount: The amount requested (default 1)
-    Want(Item, Option<Amount>),
+    /// rate: The rate requested, such as 30/min or 120m3/min (default 1 per display unit)
+    Want(Item, Option<Rate>),
 
-    /// `(unwant|remove|rm) <item> [amount]`
+    /// `(unwant|remove|rm) <item> [rate]`
     /// Remove some or all of an item from the selected outputs
     ///
     /// # Arguments
     /// item: The name of the item to remove
-    /// amount: The amount to remove (default all)
-    Unwant(Item, Option<Amount>),
+    /// rate: The rate to remove (default all)
+    Unwant(Item, Option<Rate>),
+
+    /// `have <item> [rate]`
+    /// Add an item to the available inputs
+    ///
+    /// # Arguments
+    /// item: The name of the item to add
+    /// rate: The rate available, such as 480/min or 300m3/min (default 1 per display unit)
+    Have(Item, Option<Rate>),
+
+    /// `unhave <item> [rate]`
+    /// Remove some or all of an item from the available inputs
+    ///
+    /// # Arguments
+    /// item: The name of the item to remove
+    /// rate: The rate to remove (default all)
+    Unhave(Item, Option<Rate>),
+
+    /// `unit [unit]`
+    /// Show or set the time unit rates are displayed in
+    ///
+    /// # Arguments
+    /// unit: One of s, min or h
+    Unit(Option<TimeUnit>),
+
+    /// `enable <recipe>`
+    /// Allow the planner to use a recipe, such as an unlocked alternate
+    ///
+    /// # Arguments
+    /// recipe: The name of the recipe, as listed by 'recipes'
+    Enable(RecipeName),
+
+    /// `disable <recipe>`
+    /// Stop the planner from using a recipe
+    ///
+    /// # Arguments
+    /// recipe: The name of the recipe, as listed by 'recipes'
+    Disable(RecipeName),
+
+    /// `clock <speed> [target]`
+    /// Set the clock speed machines are planned at
+    ///
+    /// # Arguments
+    /// speed: A percentage from 1% to 250%
+    /// target: Only set the clock speed for this machine, or for this recipe over its machine's
+    Clock(ClockSpeed, Option<ClockTarget>),
+
+    /// `(maxclock|cap) <speed>`
+    /// Set the highest clock speed used when minimizing buildings
+    ///
+    /// # Arguments
+    /// speed: A percentage from 1% to 250%
+    MaxClock(ClockSpeed),
+
+    /// `whole [enabled]`
+    /// Show or set whether plans use a whole number of machines per recipe
+    ///
+    /// # Arguments
+    /// enabled: true to solve for whole machines directly (slow for large plans)
+    Whole(Option<bool>),
+
+    /// `(byproduct|bp) <item> [policy]`
+    /// Show or set what plans may do with excess of an item
+    ///
+    /// # Arguments
+    /// item: The name of the item
+    /// policy: One of consume, sink or surplus (default surplus)
+    Byproduct(Item, Option<ByproductPolicy>),
+
+    /// `tier <conveyor> [tier]`
+    /// Show the tiers of belt or pipe, or set the highest one unlocked
+    ///
+    /// # Arguments
+    /// conveyor: Either belt or pipe
+    /// tier: The name of the highest tier plans may use, as listed by 'tier'
+    Tier(Conveyor, Option<String>),
+
+    /// `recipes [item]`
+    /// List recipes and whether they are enabled
+    ///
+    /// # Arguments
+    /// item: Only list recipes producing this item
+    Recipes(Option<Item>),
+
+    /// `items [item]`
+    /// List the items described in the config
+    ///
+    /// # Arguments
+    /// item: Only describe this item
+    Items(Option<Item>),
+
+    /// `cycles`
+    /// List the loops among the enabled recipes, where items can be made from
+    /// their own products
+    Cycles,
+
+    /// `machines`
+    /// List the machines declared in the config
+    Machines,
 
     /// `show`
     /// Show the current configuration
     Show,
 
-    /// `calculate`
+    /// `(calculate|calc) [minimize]`
     /// Compute the build strategy for the currently selected outputs
-    Calculate,
+    ///
+    /// # Arguments
+    /// minimize: What to minimize: raw (default), power or buildings, optionally after --minimize
+    Calculate(Option<Objective>),
+
+    /// `export <format> <file>`
+    /// Write the last calculated plan to a file
+    ///
+    /// # Arguments
+    /// format: One of dot for a Graphviz graph, md for a Markdown report or html for a web page
+    /// file: The path of the file to write
+    Export(Format, PathBuf),
+
+    /// `undo`
+    /// Revert the last command that changed the outputs, inputs or settings
+    Undo,
+
+    /// `redo`
+    /// Reapply the last command reverted by undo
+    Redo,
+
+    /// `history`
+    /// List the commands that can be undone or redone
+    History,
+
+    /// `save <file>`
+    /// Write the selected outputs, available inputs and settings to a file
+    ///
+    /// # Arguments
+    /// file: The path of the YAML file to write
+    Save(PathBuf),
+
+    /// `load <file>`
+    /// Replace the selected outputs, available inputs and settings with those
+    /// in a file written by save
+    ///
+    /// # Arguments
+    /// file: The path of the YAML file to read
+    Load(PathBuf),
+
+    /// `(maximize|max) <targets...>`
+    /// Compute the most of some items that can be made from the available
+    /// inputs, along with the selected outputs
+    ///
+    /// # Arguments
+    /// targets: The items to maximize, each optionally weighted, such as screw:2
+    Maximize(Vec<Target>),
+}
+
+impl BaseCommand {
+    /// Whether the command changes the outputs, inputs or settings, and so can
+    /// be undone
+    pub fn changes_state(&self) -> bool {
+        match self {
+            Self::Want(..)
+            | Self::Unwant(..)
+            | Self::Have(..)
+            | Self::Unhave(..)
+            | Self::Unit(Some(_))
+            | Self::Enable(_)
+            | Self::Disable(_)
+            | Self::Clock(..)
+            | Self::MaxClock(_)
+            | Self::Whole(Some(_))
+            | Self::Byproduct(_, Some(_))
+            | Self::Tier(_, Some(_))
+            | Self::Load(_) => true,
+            Self::Unit(None)
+            | Self::Whole(None)
+            | Self::Byproduct(_, None)
+            | Self::Tier(_, None)
+            | Self::Recipes(_)
+            | Self::Items(_)
+            | Self::Cycles
+            | Self::Machines
+            | Self::Show
+            | Self::Calculate(_)
+            | Self::Export(..)
+            | Self::Undo
+            | Self::Redo
+            | Self::History
+            | Self::Save(_)
+            | Self::Maximize(_) => false,
+        }
+    }
 }
 
 type Formatted = Result<String, fmt::Error>;
@@ -110,5 +295,31 @@ impl FoldError for FormatError {
 }
 
 pub fn parse_str(s: impl AsRef<str>) -> Result<BaseCommand, docbot::CommandParseError> {
-    BaseCommand::parse(docbot::tokenize_str_simple(s.as_ref()))
+    BaseCommand::parse(docbot::tokenize_str_simple(&minimize_option(s.as_ref())))
+}
+
+/// Rewrite `calculate --minimize <objective>`, as the objective is given on the
+/// command line, into the positional form the command is declared with
+fn minimize_option(s: &str) -> Cow<'_, str> {
+    let words: Vec<_> = s.split_whitespace().collect();
+
+    let (cmd, objective, rest) = match words[..] {
+        [cmd @ ("calculate" | "calc"), "--minimize" | "-m", objective, ref rest @ ..] => {
+            (cmd, objective, rest)
+        },
+        [cmd @ ("calculate" | "calc"), opt, ref rest @ ..] => {
+            match opt.strip_prefix("--minimize=") {
+                Some(objective) => (cmd, objective, rest),
+                None => return s.into(),
+            }
+        },
+        _ => return s.into(),
+    };
+
+    [cmd, objective]
+        .into_iter()
+        .chain(rest.iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .into()
 }
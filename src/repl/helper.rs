@@ -0,0 +1,334 @@
+use std::borrow::Cow;
+
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context,
+};
+
+use crate::{
+    items::{Item, Machine, RecipeName},
+    prelude::*,
+};
+
+/// What can be typed for an argument of a command
+#[derive(Debug, Clone, Copy)]
+enum Arg {
+    /// Anything, with nothing to complete
+    Any,
+    Item,
+    Recipe,
+    /// A machine, or a recipe to set apart from its machine
+    MachineOrRecipe,
+    /// One of a fixed set of words
+    Word(&'static [&'static str]),
+    /// This and every later argument is an item, optionally weighted such as
+    /// `screw:2`
+    Targets,
+}
+
+const UNITS: Arg = Arg::Word(&["s", "min", "h"]);
+const BOOLS: Arg = Arg::Word(&["true", "false"]);
+const POLICIES: Arg = Arg::Word(&["consume", "sink", "surplus"]);
+const CONVEYORS: Arg = Arg::Word(&["belt", "pipe"]);
+const OBJECTIVES: Arg = Arg::Word(&["raw", "power", "buildings"]);
+const FORMATS: Arg = Arg::Word(&["dot", "md", "html"]);
+
+/// Every command name and alias, with the arguments it takes.  This must be
+/// kept in step with [`BaseCommand`](super::command::BaseCommand), which the
+/// `commands_listed` test checks by reading the usage line that starts the
+/// docs of each variant, so those lines must keep the form `` `(name|alias)
+/// args` ``.
+const COMMANDS: &[(&str, &[Arg])] = &[
+    ("want", &[Arg::Item]),
+    ("add", &[Arg::Item]),
+    ("unwant", &[Arg::Item]),
+    ("remove", &[Arg::Item]),
+    ("rm", &[Arg::Item]),
+    ("have", &[Arg::Item]),
+    ("unhave", &[Arg::Item]),
+    ("unit", &[UNITS]),
+    ("enable", &[Arg::Recipe]),
+    ("disable", &[Arg::Recipe]),
+    ("clock", &[Arg::Any, Arg::MachineOrRecipe]),
+    ("maxclock", &[]),
+    ("cap", &[]),
+    ("whole", &[BOOLS]),
+    ("byproduct", &[Arg::Item, POLICIES]),
+    ("bp", &[Arg::Item, POLICIES]),
+    ("tier", &[CONVEYORS]),
+    ("recipes", &[Arg::Item]),
+    ("items", &[Arg::Item]),
+    ("cycles", &[]),
+    ("machines", &[]),
+    ("show", &[]),
+    ("calculate", &[OBJECTIVES]),
+    ("calc", &[OBJECTIVES]),
+    ("export", &[FORMATS]),
+    ("undo", &[]),
+    ("redo", &[]),
+    ("history", &[]),
+    ("save", &[]),
+    ("load", &[]),
+    ("maximize", &[Arg::Targets]),
+    ("max", &[Arg::Targets]),
+];
+
+const RED: &str = "\x1b[31m";
+const GREY: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+/// Each whitespace-separated word of a line, with its byte offset
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut start = None;
+
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                words.push((s, &line[s..i]));
+                start = None;
+            },
+            _ => (),
+        }
+    }
+
+    words
+}
+
+/// What argument `index` of the command `cmd` is, if the command exists
+fn arg(cmd: &str, index: usize) -> Option<Arg> {
+    let (_, args) = COMMANDS.iter().find(|(name, _)| *name == cmd)?;
+
+    args.get(index)
+        .or_else(|| args.last().filter(|a| matches!(a, Arg::Targets)))
+        .copied()
+}
+
+/// Completes, hints and highlights REPL input using the command names and the
+/// names of the items, machines and recipes in the config
+#[derive(Debug)]
+pub struct Helper {
+    items: Vec<String>,
+    machines: Vec<String>,
+    recipes: Vec<String>,
+}
+
+impl Helper {
+    /// Create a helper for the items, machines and recipes loaded so far
+    pub fn new() -> Self {
+        let mut items = Item::registry_ref().values();
+        let mut machines = Machine::registry_ref().values();
+        let mut recipes = RecipeName::registry_ref().values();
+        items.sort();
+        machines.sort();
+        recipes.sort();
+
+        Self {
+            items,
+            machines,
+            recipes,
+        }
+    }
+
+    /// The byte offset of the word ending at the end of `line`, and every
+    /// value it could be completed to
+    fn candidates<'a>(&'a self, line: &str) -> (usize, Vec<&'a str>) {
+        let mut words = words(line);
+
+        let (start, word) = if line.ends_with(char::is_whitespace) || line.is_empty() {
+            (line.len(), "")
+        } else {
+            words.pop().unwrap_or_default()
+        };
+
+        let names: Vec<&str> = match words.first() {
+            None => COMMANDS.iter().map(|(name, _)| *name).collect(),
+            Some((_, cmd)) => match arg(cmd, words.len() - 1) {
+                Some(Arg::Item) => self.items.iter().map(String::as_str).collect(),
+                Some(Arg::Targets) if !word.contains(':') => {
+                    self.items.iter().map(String::as_str).collect()
+                },
+                Some(Arg::Recipe) => self.recipes.iter().map(String::as_str).collect(),
+                Some(Arg::MachineOrRecipe) => self
+                    .machines
+                    .iter()
+                    .chain(&self.recipes)
+                    .map(String::as_str)
+                    .collect(),
+                Some(Arg::Word(words)) => words.to_vec(),
+                Some(Arg::Any | Arg::Targets) | None => vec![],
+            },
+        };
+
+        let names = names.into_iter().filter(|n| n.starts_with(word)).collect();
+
+        (start, names)
+    }
+
+    /// Whether `word`, typed as an item, names no known item.  The word being
+    /// typed at the cursor only counts once no item starts with it.
+    fn is_unknown(&self, word: &str, typing: bool) -> bool {
+        if self.items.binary_search_by(|i| i.as_str().cmp(word)).is_ok() {
+            return false;
+        }
+
+        !(typing && self.items.iter().any(|i| i.starts_with(word)))
+    }
+}
+
+impl Completer for Helper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let (start, names) = self.candidates(&line[..pos]);
+
+        Ok((start, names.into_iter().map(ToOwned::to_owned).collect()))
+    }
+}
+
+impl Hinter for Helper {
+    type Hint = String;
+
+    /// Hints the rest of the word at the end of the line if only one value
+    /// could complete it
+    fn hint(&self, line: &str, pos: usize, _: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+
+        let (start, names) = self.candidates(line);
+        let typed = line.len() - start;
+
+        match names[..] {
+            [name] if typed > 0 && name.len() > typed => Some(name[typed..].to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl Highlighter for Helper {
+    /// Highlights item names that don't exist in the config
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        let words = words(line);
+        let cmd = match words.first() {
+            Some((_, cmd)) => *cmd,
+            None => return Cow::Borrowed(line),
+        };
+
+        let mut out = String::new();
+        let mut end = 0;
+
+        for (i, (start, word)) in words.iter().enumerate().skip(1) {
+            let item = match arg(cmd, i - 1) {
+                Some(Arg::Item) => *word,
+                Some(Arg::Targets) => word.split(':').next().unwrap_or_default(),
+                _ => continue,
+            };
+
+            let typing = pos == start + word.len();
+
+            if self.is_unknown(item, typing) {
+                out.push_str(&line[end..*start]);
+                out.push_str(RED);
+                out.push_str(item);
+                out.push_str(RESET);
+                end = start + item.len();
+            }
+        }
+
+        if end == 0 {
+            return Cow::Borrowed(line);
+        }
+
+        out.push_str(&line[end..]);
+
+        Cow::Owned(out)
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("{}{}{}", GREY, hint, RESET))
+    }
+
+    fn highlight_char(&self, _: &str, _: usize) -> bool { true }
+}
+
+impl Validator for Helper {}
+
+impl rustyline::Helper for Helper {}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::COMMANDS;
+
+    /// The source the command names are declared in
+    const SOURCE: &str = include_str!("command.rs");
+    /// A usage line, capturing the command's names
+    const USAGE: &str = r"(?m)^    /// `\(?([\w|]+)\)?[ `]";
+
+    /// Every name docbot parses as a command, read from the usage line that
+    /// starts the docs of each variant of `BaseCommand`
+    fn command_names(source: &str) -> Vec<&str> {
+        let usage = Regex::new(USAGE).unwrap();
+
+        usage
+            .captures_iter(source)
+            .flat_map(|c| c.get(1).unwrap().as_str().split('|'))
+            .collect()
+    }
+
+    #[test]
+    fn usage_lines() {
+        let source = "
+pub enum BaseCommand {
+    /// `(want|add) <item> [rate]`
+    /// Add an item to the selected outputs
+    ///
+    /// # Arguments
+    /// item: The name of the item to add
+    Want(Item, Option<Rate>),
+
+    /// `cycles`
+    /// List the loops recipes could form, such as `(a|b) c`
+    Cycles,
+}
+";
+
+        assert_eq!(command_names(source), ["want", "add", "cycles"]);
+    }
+
+    /// Every variant of `BaseCommand` must have a usage line in the form
+    /// [`command_names`] reads, or its names would go unchecked
+    #[test]
+    fn usage_per_variant() {
+        let start = SOURCE.find("pub enum BaseCommand {").unwrap();
+        let body = &SOURCE[start..start + SOURCE[start..].find("\n}\n").unwrap()];
+        let variants = Regex::new(r"(?m)^    [A-Z]\w*[(,]").unwrap();
+        let usage = Regex::new(USAGE).unwrap();
+
+        assert_eq!(usage.find_iter(body).count(), variants.find_iter(body).count());
+    }
+
+    #[test]
+    fn commands_listed() {
+        let names = command_names(SOURCE);
+
+        for name in &names {
+            assert!(
+                COMMANDS.iter().any(|(n, _)| n == name),
+                "{} is missing from COMMANDS",
+                name
+            );
+        }
+
+        for (name, _) in COMMANDS {
+            assert!(names.contains(name), "{} is not a command", name);
+        }
+    }
+}
@@ -1,25 +1,69 @@
 mod command;
+mod helper;
 mod readline;
 
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    mem,
+    path::{Path, PathBuf},
+};
+
 use command::BaseCommand;
 use num_traits::identities::one;
 use readline::Editor;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    cli::Opts,
-    config::Config,
-    items::{ItemStack, ItemStacks},
+    cli::{ExecCommand, Opts},
+    config::{Config, Conveyor, KeyedRecipe},
+    export::{self, Format, Output},
+    items::{Amount, Item, ItemStack, ItemStacks, Rate, TimeUnit},
+    plan::{self, Objective, Plan, Settings},
     prelude::*,
 };
 
 pub fn run(opts: Opts) -> Result<()> {
-    let Opts { config } = opts;
+    let Opts {
+        config,
+        dot,
+        format,
+        script,
+        echo,
+        keep_going,
+        ..
+    } = opts;
     let config = Config::load(config).context("Failed to load config")?;
 
     debug!("Loaded config: {:#?}", config);
 
+    let mut session = Session {
+        config,
+        dot,
+        output: format,
+        state: State::new(),
+        undo: VecDeque::new(),
+        redo: vec![],
+    };
+
+    if let Some(path) = script {
+        let file = File::open(&path)
+            .with_context(|| anyhow!("Failed to open script {}", path.display()))?;
+
+        return session.run_script(
+            BufReader::new(file),
+            &path.display().to_string(),
+            echo,
+            keep_going,
+        );
+    }
+
+    if !atty::is(atty::Stream::Stdin) {
+        return session.run_script(io::stdin().lock(), "<stdin>", echo, keep_going);
+    }
+
     let mut rl = Editor::new();
-    let mut state = State::new();
 
     loop {
         use rustyline::error::ReadlineError;
@@ -38,7 +82,7 @@ pub fn run(opts: Opts) -> Result<()> {
 
         let cmd = match command::parse_str(&line) {
             Ok(cmd) => {
-                rl.add_history_entry(line);
+                rl.add_history_entry(line.as_str());
 
                 trace!("{:?}", cmd);
 
@@ -60,40 +104,762 @@ pub fn run(opts: Opts) -> Result<()> {
             },
         };
 
-        if let Ok(next) = state
+        session
+            .exec(cmd, line.trim())
+            .unwrap_or_else(|e| error!("Command failed: {:?}", e));
+    }
+}
+
+/// The most changes to the state that can be undone
+const UNDO_LIMIT: usize = 100;
+
+/// Everything needed to run commands from the REPL or a script
+struct Session {
+    config: Config,
+    dot: Option<PathBuf>,
+    output: Output,
+    state: State,
+    /// Each command that changed the state, with the state before it ran
+    undo: VecDeque<(String, State)>,
+    /// Each undone command, with the state after it ran
+    redo: Vec<(String, State)>,
+}
+
+impl Session {
+    /// Run a command, keeping the state unchanged if it fails.  `line` is the
+    /// text of the command, for listing in its history.
+    fn exec(&mut self, cmd: BaseCommand, line: &str) -> Result<()> {
+        match cmd {
+            BaseCommand::Undo => return self.undo(),
+            BaseCommand::Redo => return self.redo(),
+            BaseCommand::History => {
+                self.print_history();
+                return Ok(());
+            },
+            _ => (),
+        }
+
+        let solved = matches!(cmd, BaseCommand::Calculate(_) | BaseCommand::Maximize(_));
+        let changes = cmd.changes_state();
+        let next = self
+            .state
             .clone()
-            .handle_cmd(cmd)
-            .map_err(|e| error!("Command failed: {:?}", e))
-        {
-            state = next;
+            .handle_cmd(&self.config, self.output, cmd)?;
+        let prev = mem::replace(&mut self.state, next);
+
+        if changes {
+            if self.undo.len() == UNDO_LIMIT {
+                self.undo.pop_front();
+            }
+
+            self.undo.push_back((line.to_owned(), prev));
+            self.redo.clear();
         }
+
+        if let (true, Some(path), Some(plan)) = (solved, &self.dot, &self.state.plan) {
+            export::write(path, Format::Dot, plan, &self.config, self.state.unit)
+                .unwrap_or_else(|e| error!("Failed to export plan: {:?}", e));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<()> {
+        let (line, prev) = self.undo.pop_back().context("There is nothing to undo")?;
+
+        self.redo.push((line.clone(), mem::replace(&mut self.state, prev)));
+        println!("Undid {}", line);
+
+        Ok(())
+    }
+
+    fn redo(&mut self) -> Result<()> {
+        let (line, next) = self.redo.pop().context("There is nothing to redo")?;
+
+        self.undo.push_back((line.clone(), mem::replace(&mut self.state, next)));
+        println!("Redid {}", line);
+
+        Ok(())
+    }
+
+    fn print_history(&self) {
+        if self.undo.is_empty() && self.redo.is_empty() {
+            println!("No changes have been made");
+        }
+
+        for (line, _) in &self.undo {
+            println!("  {}", line);
+        }
+
+        for (line, _) in self.redo.iter().rev() {
+            println!("  {} (undone)", line);
+        }
+    }
+
+    /// Run the commands in a script one per line, skipping blank lines and
+    /// anything after a `#`.  Errors are reported with the script's `name`
+    /// and the line number, and stop the script unless `keep_going` is set.
+    fn run_script(
+        &mut self,
+        input: impl BufRead,
+        name: &str,
+        echo: bool,
+        keep_going: bool,
+    ) -> Result<()> {
+        let mut failed = 0_usize;
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line.with_context(|| anyhow!("Failed to read {}", name))?;
+            let text = line.split('#').next().unwrap_or_default().trim();
+
+            if text.is_empty() {
+                continue;
+            }
+
+            if echo {
+                println!("» {}", text);
+            }
+
+            let res = command::parse_str(text)
+                .map_err(|e| anyhow!(command::FormatError.fold_command_parse(e).unwrap()))
+                .and_then(|cmd| self.exec(cmd, text));
+
+            if let Err(e) = res {
+                eprintln!("{}:{}: {:#}", name, i + 1, e);
+
+                if !keep_going {
+                    bail!("Stopped at the error on line {} of {}", i + 1, name);
+                }
+
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            bail!("{} command(s) in {} failed", failed, name);
+        }
+
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+/// Parse an item given on the command line, with an optional rate first,
+/// such as `60 screw`
+fn parse_stack(s: &str) -> Result<(Item, Option<Rate>)> {
+    let words: Vec<_> = s.split_whitespace().collect();
+
+    Ok(match words[..] {
+        [item] => (item.parse()?, None),
+        [rate, item] => (item.parse()?, Some(rate.parse()?)),
+        _ => bail!("Expected an item with an optional rate first, such as \"60 screw\""),
+    })
+}
+
+/// Run a subcommand non-interactively, handling it as the equivalent REPL
+/// commands would be
+pub fn exec(opts: Opts, command: ExecCommand) -> Result<()> {
+    let Opts {
+        config: path,
+        dot,
+        format,
+        script,
+        echo,
+        keep_going,
+        ..
+    } = opts;
+
+    let repl_only = [
+        ("--dot", dot.is_some()),
+        ("--script", script.is_some()),
+        ("--echo", echo),
+        ("--keep-going", keep_going),
+    ];
+
+    if let Some((flag, _)) = repl_only.iter().find(|(_, set)| *set) {
+        bail!("{} only applies to the REPL, not to subcommands", flag);
+    }
+
+    let config = Config::load(&path).context("Failed to load config")?;
+
+    let cmds = match command {
+        ExecCommand::Plan {
+            want,
+            have,
+            enable,
+            minimize,
+        } => {
+            let mut cmds = vec![];
+
+            for s in want {
+                let (item, rate) =
+                    parse_stack(&s).with_context(|| anyhow!("Invalid output {:?}", s))?;
+                cmds.push(BaseCommand::Want(item, rate));
+            }
+
+            for s in have {
+                let (item, rate) =
+                    parse_stack(&s).with_context(|| anyhow!("Invalid input {:?}", s))?;
+                cmds.push(BaseCommand::Have(item, rate));
+            }
+
+            for s in enable {
+                cmds.push(BaseCommand::Enable(s.parse()?));
+            }
+
+            cmds.push(BaseCommand::Calculate(minimize));
+            cmds
+        },
+        ExecCommand::Recipes { item } => {
+            vec![BaseCommand::Recipes(item.map(|i| i.parse()).transpose()?)]
+        },
+        ExecCommand::Items { item } => {
+            vec![BaseCommand::Items(item.map(|i| i.parse()).transpose()?)]
+        },
+        ExecCommand::Validate => {
+            println!(
+                "{} is valid: {} items, {} recipes, {} machines",
+                path.display(),
+                config.items().len(),
+                config.recipes().len(),
+                config.machines().len()
+            );
+
+            // List the loops any combination of recipes could form
+            config
+                .recipes()
+                .iter()
+                .filter_map(|r| r.id())
+                .map(BaseCommand::Enable)
+                .chain([BaseCommand::Cycles])
+                .collect()
+        },
+    };
+
+    cmds.into_iter()
+        .try_fold(State::new(), |state, cmd| state.handle_cmd(&config, format, cmd))?;
+
+    Ok(())
+}
+
+/// Everything set up in a session.  Items and recipes are saved by name, and
+/// rates per minute regardless of the display unit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct State {
+    #[serde(default = "ItemStacks::empty")]
     want: ItemStacks,
+    #[serde(default = "ItemStacks::empty")]
+    have: ItemStacks,
+    #[serde(default)]
+    unit: TimeUnit,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(skip)]
+    plan: Option<Plan>,
+}
+
+/// The output of `show`, serialized with the same schema version as plans
+#[derive(Debug, Serialize)]
+struct StateReport {
+    schema_version: u32,
+    unit: String,
+    want: BTreeMap<String, Amount>,
+    have: BTreeMap<String, Amount>,
+    byproducts: BTreeMap<String, String>,
+}
+
+impl StateReport {
+    fn new(state: &State) -> Self {
+        let rates = |stacks: &ItemStacks| {
+            stacks
+                .iter()
+                .map(|(item, amt)| (item.to_string(), state.unit.convert(amt)))
+                .collect()
+        };
+
+        Self {
+            schema_version: plan::SCHEMA_VERSION,
+            unit: state.unit.to_string(),
+            want: rates(&state.want),
+            have: rates(&state.have),
+            byproducts: state
+                .settings
+                .byproducts()
+                .iter()
+                .map(|(item, policy)| (item.to_string(), policy.to_string()))
+                .collect(),
+        }
+    }
 }
 
 impl State {
     fn new() -> Self {
         Self {
             want: ItemStacks::empty(),
+            have: ItemStacks::empty(),
+            unit: TimeUnit::default(),
+            settings: Settings::default(),
+            plan: None,
         }
     }
 
-    fn handle_cmd(mut self, cmd: BaseCommand) -> Result<Self> {
+    fn save(&self, path: &Path) -> Result<()> {
+        let yaml = serde_yaml::to_string(self).context("Failed to serialize session")?;
+
+        fs::write(path, yaml).with_context(|| anyhow!("Failed to write {}", path.display()))
+    }
+
+    /// Read a session written by `save`.  Item and recipe names are checked
+    /// against the current config as they are read.
+    fn load(config: &Config, path: &Path) -> Result<Self> {
+        let file = File::open(path).context("Failed to open file")?;
+        let state: Self = serde_yaml::from_reader(file)?;
+
+        for conveyor in [Conveyor::Belt, Conveyor::Pipe] {
+            if let Some(tier) = state.settings.max_tier(conveyor) {
+                if config.logistics().tier(conveyor, tier).is_none() {
+                    bail!("There is no {} tier named {:?}", conveyor, tier);
+                }
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Convert a rate of `item` given on the command line into an amount per
+    /// minute
+    fn per_min(&self, config: &Config, item: Item, rate: Option<Rate>) -> Result<Amount> {
+        let rate = rate.unwrap_or_else(|| Rate::new(one(), None));
+        config.check_rate(item, &rate)?;
+
+        Ok(rate.per_min_or(self.unit))
+    }
+
+    /// Convert a rate of `item` to remove from `stacks` into an amount per
+    /// minute, taking all of it if no rate is given
+    fn removal(
+        &self,
+        config: &Config,
+        stacks: &ItemStacks,
+        item: Item,
+        rate: Option<Rate>,
+    ) -> Result<Amount> {
+        match rate {
+            Some(rate) => self.per_min(config, item, Some(rate)),
+            None => Ok(stacks.get(&item).cloned().unwrap_or_else(one)),
+        }
+    }
+
+    fn handle_cmd(mut self, config: &Config, output: Output, cmd: BaseCommand) -> Result<Self> {
         match cmd {
-            BaseCommand::Want(item, amt) => self.want += ItemStack(item, amt.unwrap_or_else(one)),
-            // TODO: add and use a saturating_remove for this
-            BaseCommand::Unwant(item, amt) => self
-                .want
-                .try_remove_one(ItemStack(item, amt.unwrap_or_else(one)))
-                .context("Failed to remove item from wanted list")?,
-            BaseCommand::Show => println!("{:#?}", self),
-            BaseCommand::Calculate => todo!(),
+            BaseCommand::Want(item, rate) => {
+                let amt = self.per_min(config, item, rate)?;
+                self.want += ItemStack(item, amt);
+            },
+            BaseCommand::Unwant(item, rate) => {
+                let amt = self.removal(config, &self.want, item, rate)?;
+                self.want
+                    .try_remove_one(ItemStack(item, amt))
+                    .context("Failed to remove item from wanted list")?;
+            },
+            BaseCommand::Have(item, rate) => {
+                let amt = self.per_min(config, item, rate)?;
+                self.have += ItemStack(item, amt);
+            },
+            BaseCommand::Unhave(item, rate) => {
+                let amt = self.removal(config, &self.have, item, rate)?;
+                self.have
+                    .try_remove_one(ItemStack(item, amt))
+                    .context("Failed to remove item from available inputs")?;
+            },
+            BaseCommand::Enable(id) => self.settings.set_enabled(id, true),
+            BaseCommand::Disable(id) => self.settings.set_enabled(id, false),
+            BaseCommand::Clock(clock, target) => self.settings.set_clock(clock, target),
+            BaseCommand::MaxClock(clock) => self.settings.set_max_clock(clock),
+            BaseCommand::Whole(None) => println!(
+                "Solving for whole machines is {}",
+                if self.settings.whole() { "on" } else { "off" }
+            ),
+            BaseCommand::Whole(Some(whole)) => self.settings.set_whole(whole),
+            BaseCommand::Byproduct(item, None) => println!(
+                "Excess {} is handled by policy {}",
+                config.item_name(item),
+                self.settings.byproduct(item)
+            ),
+            BaseCommand::Byproduct(item, Some(policy)) => {
+                self.settings.set_byproduct(item, policy);
+            },
+            BaseCommand::Tier(conveyor, None) => self.print_tiers(config, conveyor),
+            BaseCommand::Tier(conveyor, Some(tier)) => {
+                if config.logistics().tier(conveyor, &tier).is_none() {
+                    bail!("There is no {} tier named {:?}", conveyor, tier);
+                }
+
+                self.settings.set_max_tier(conveyor, tier);
+            },
+            BaseCommand::Recipes(item) => self.print_recipes(config, item),
+            BaseCommand::Items(item) => print_items(config, item),
+            BaseCommand::Cycles => print_cycles(config, &self.settings),
+            BaseCommand::Machines => print_machines(config),
+            BaseCommand::Unit(None) => println!("Rates are displayed per {}", self.unit),
+            BaseCommand::Unit(Some(unit)) => self.unit = unit,
+            BaseCommand::Show => self.print_state(config, output)?,
+            BaseCommand::Calculate(objective) => {
+                let plan = Plan::calculate(
+                    config,
+                    &self.want,
+                    &self.have,
+                    &self.settings,
+                    objective.unwrap_or_default(),
+                )
+                .context("Failed to calculate a production plan")?;
+
+                self.print_plan(config, output, &plan)?;
+                self.plan = Some(plan);
+            },
+            BaseCommand::Maximize(targets) => {
+                let plan = Plan::maximize(
+                    config,
+                    &targets,
+                    &self.want,
+                    &self.have,
+                    &self.settings,
+                    Objective::default(),
+                )
+                .context("Failed to calculate a production plan")?;
+
+                self.print_plan(config, output, &plan)?;
+                self.plan = Some(plan);
+            },
+            BaseCommand::Undo | BaseCommand::Redo | BaseCommand::History => {
+                bail!("History is only kept in the REPL")
+            },
+            BaseCommand::Save(path) => {
+                self.save(&path)?;
+                println!("Saved session to {}", path.display());
+            },
+            BaseCommand::Load(path) => {
+                self = Self::load(config, &path)
+                    .with_context(|| anyhow!("Failed to load session from {}", path.display()))?;
+                println!("Loaded session from {}", path.display());
+            },
+            BaseCommand::Export(format, path) => {
+                let plan = self
+                    .plan
+                    .as_ref()
+                    .context("No plan has been calculated yet")?;
+
+                export::write(&path, format, plan, config, self.unit)?;
+                println!("Wrote plan to {}", path.display());
+            },
         }
 
         Ok(self)
     }
+
+    fn print_plan(&self, config: &Config, output: Output, plan: &Plan) -> Result<()> {
+        match output.serialize(&plan.report(config, self.unit))? {
+            Some(s) => println!("{}", s.trim_end()),
+            None => print!("{}", plan.display(config, self.unit)),
+        }
+
+        Ok(())
+    }
+
+    fn print_state(&self, config: &Config, output: Output) -> Result<()> {
+        if let Some(s) = output.serialize(&StateReport::new(self))? {
+            println!("{}", s.trim_end());
+            return Ok(());
+        }
+
+        let mut s = String::new();
+        plan::write_rates(&mut s, config, "Wanted", &self.want, self.unit)?;
+        plan::write_rates(&mut s, config, "Available", &self.have, self.unit)?;
+
+        let mut policies: Vec<_> = self.settings.byproducts().iter().collect();
+        policies.sort_by_cached_key(|(i, _)| config.item_name(**i));
+
+        println!("Rates are displayed per {}", self.unit);
+        print!("{}", s);
+
+        if !policies.is_empty() {
+            println!("Byproducts:");
+
+            for (item, policy) in policies {
+                println!("  {}: {}", config.item_name(*item), policy);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print_tiers(&self, config: &Config, conveyor: Conveyor) {
+        let unlocked = config
+            .logistics()
+            .unlocked(conveyor, self.settings.max_tier(conveyor));
+
+        for tier in config.logistics().tiers(conveyor) {
+            let mark = if unlocked.contains(tier) { 'x' } else { ' ' };
+
+            println!(
+                "  [{}] {}: {}{}/{}",
+                mark,
+                tier.name(),
+                self.unit.convert(tier.capacity()),
+                match conveyor {
+                    Conveyor::Belt => "",
+                    Conveyor::Pipe => " m³",
+                },
+                self.unit
+            );
+        }
+    }
+
+    fn print_recipes(&self, config: &Config, item: Option<Item>) {
+        let mut recipes: Vec<_> = match item {
+            Some(item) => config
+                .keyed_recipes()
+                .get(&item)
+                .into_iter()
+                .flatten()
+                .map(KeyedRecipe::recipe)
+                .collect(),
+            None => config.recipes().iter().collect(),
+        };
+
+        recipes.sort_by_cached_key(|r| (r.machine().to_string(), r.to_string()));
+
+        for recipe in recipes {
+            let mark = if self.settings.is_enabled(recipe) {
+                'x'
+            } else {
+                ' '
+            };
+
+            match recipe.id() {
+                Some(id) if recipe.alternate() => {
+                    print!("  [{}] {} (alternate, {})", mark, id, recipe.machine());
+                },
+                Some(id) => print!("  [{}] {} ({})", mark, id, recipe.machine()),
+                None => print!("  [{}] ({})", mark, recipe.machine()),
+            }
+
+            println!(
+                ": {} from {}",
+                config.named(recipe.outputs()),
+                config.named(recipe.inputs())
+            );
+        }
+    }
+}
+
+fn print_machines(config: &Config) {
+    let mut machines: Vec<_> = config.machines().iter().collect();
+    machines.sort_by_cached_key(|(m, _)| m.to_string());
+
+    for (machine, info) in machines {
+        print!("  {}: {} MW", machine, info.power());
+
+        if let Some((width, length)) = info.footprint() {
+            print!(", {}x{} m", width, length);
+        }
+
+        match info.ports() {
+            (Some(i), Some(o)) => print!(", {} in/{} out", i, o),
+            (Some(i), None) => print!(", {} in", i),
+            (None, Some(o)) => print!(", {} out", o),
+            (None, None) => (),
+        }
+
+        if !info.cost().is_empty() {
+            print!(", costs {}", info.cost());
+        }
+
+        println!();
+    }
+}
+
+fn print_items(config: &Config, item: Option<Item>) {
+    let mut items: Vec<_> = match item {
+        Some(item) => config.items().get_key_value(&item).into_iter().collect(),
+        None => config.items().iter().collect(),
+    };
+    items.sort_by_cached_key(|(i, _)| i.to_string());
+
+    if items.is_empty() {
+        println!("No matching items are described in the config");
+    }
+
+    for (item, info) in items {
+        print!("  {}: {}, {}", item, config.item_name(*item), info.form());
+
+        if let Some(size) = info.stack_size() {
+            print!(", stacks of {}", size);
+        }
+
+        if let Some(points) = info.sink_points() {
+            print!(", {} sink points", points);
+        }
+
+        if let Some(category) = info.category() {
+            print!(" ({})", category);
+        }
+
+        println!();
+    }
+}
+
+fn print_cycles(config: &Config, settings: &Settings) {
+    let cycles = plan::cycles::find(config, settings);
+
+    if cycles.is_empty() {
+        println!("No enabled recipes form a loop");
+    }
+
+    for cycle in cycles {
+        let items: Vec<_> = cycle.items().iter().map(|i| config.item_name(*i)).collect();
+        println!("  {}:", items.join(", "));
+
+        for recipe in cycle.recipes() {
+            println!("    {} ({})", recipe, recipe.machine());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::load;
+
+    const CONFIG: &str = "
+raw: [iron_ore]
+machines:
+  constructor: { power: 4 }
+  smelter: { power: 4 }
+recipes:
+  constructor:
+    - { make: 2 iron_plate, from: 3 iron_ingot, in: 6 }
+  smelter:
+    - { make: 1 iron_ingot, from: 1 iron_ore, in: 2 }
+";
+
+    fn session() -> Session {
+        Session {
+            config: load(CONFIG).unwrap(),
+            dot: None,
+            output: Output::Text,
+            state: State::new(),
+            undo: VecDeque::new(),
+            redo: vec![],
+        }
+    }
+
+    fn yaml(state: &State) -> String { serde_yaml::to_string(state).unwrap() }
+
+    /// A path in the temp directory no other test or test run uses
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("satisfactorized-{}-{}.yml", name, std::process::id()))
+    }
+
+    fn wants(session: &Session) -> Vec<String> {
+        let mut wants: Vec<_> = session.state.want.keys().map(ToString::to_string).collect();
+        wants.sort();
+        wants
+    }
+
+    #[test]
+    fn script_stops() {
+        let mut session = session();
+        let script = "# Plates\nwant iron_plate\n\nwant no_such_item\nwant iron_ingot\n";
+        let err = session.run_script(script.as_bytes(), "test", false, false).unwrap_err();
+
+        assert_eq!(err.to_string(), "Stopped at the error on line 4 of test");
+        assert_eq!(wants(&session), ["iron_plate"]);
+    }
+
+    #[test]
+    fn script_keeps_going() {
+        let mut session = session();
+        let script = "want iron_plate # plates\nwant no_such_item\nwant iron_ingot\n";
+        let err = session.run_script(script.as_bytes(), "test", false, true).unwrap_err();
+
+        assert_eq!(err.to_string(), "1 command(s) in test failed");
+        assert_eq!(wants(&session), ["iron_ingot", "iron_plate"]);
+    }
+
+    #[test]
+    fn save_load() {
+        let mut session = session();
+        let script = "want iron_plate 30\nhave iron_ore 60\nunit h\nenable pure_iron_ingot\n\
+                      whole true\nbp iron_ingot sink\n";
+        session.run_script(script.as_bytes(), "test", false, false).unwrap();
+
+        let path = temp_path("save-load");
+        session.state.save(&path).unwrap();
+        let loaded = State::load(&session.config, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(yaml(&loaded.unwrap()), yaml(&session.state));
+    }
+
+    #[test]
+    fn load_unknown() {
+        let session = session();
+
+        for (name, yaml) in [
+            ("unknown-item", "want: { no_such_item: 1 }\n"),
+            ("unknown-recipe", "settings: { recipes: { no_such_recipe: true } }\n"),
+        ] {
+            let path = temp_path(name);
+            fs::write(&path, yaml).unwrap();
+            let loaded = State::load(&session.config, &path);
+            fs::remove_file(&path).unwrap();
+
+            assert!(loaded.is_err(), "{} loaded", yaml);
+        }
+    }
+
+    /// Run one command as though typed into the REPL
+    fn run(session: &mut Session, line: &str) -> Result<()> {
+        session.exec(command::parse_str(line).unwrap(), line)
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut session = session();
+        run(&mut session, "want iron_plate").unwrap();
+        let before = yaml(&session.state);
+        run(&mut session, "unit h").unwrap();
+        let after = yaml(&session.state);
+
+        run(&mut session, "undo").unwrap();
+        assert_eq!(yaml(&session.state), before);
+        run(&mut session, "redo").unwrap();
+        assert_eq!(yaml(&session.state), after);
+        assert!(run(&mut session, "redo").is_err());
+    }
+
+    #[test]
+    fn command_clears_redo() {
+        let mut session = session();
+        run(&mut session, "want iron_plate").unwrap();
+        run(&mut session, "undo").unwrap();
+        assert_eq!(session.redo.len(), 1);
+
+        run(&mut session, "want iron_ingot").unwrap();
+        assert!(session.redo.is_empty());
+        assert!(run(&mut session, "redo").is_err());
+    }
+
+    #[test]
+    fn undo_limit() {
+        let mut session = session();
+
+        for i in 0..=UNDO_LIMIT {
+            run(&mut session, &format!("want iron_plate {}", i + 1)).unwrap();
+        }
+
+        assert_eq!(session.undo.len(), UNDO_LIMIT);
+        assert_eq!(session.undo[0].0, "want iron_plate 2");
+    }
 }
@@ -1,17 +1,24 @@
 use std::ops::{Deref, DerefMut};
 
 use dispose::{Disposable, Dispose};
+use rustyline::{CompletionType, Config};
 
+use super::helper::Helper;
 use crate::prelude::*;
 
-type Rl = rustyline::Editor<()>;
+type Rl = rustyline::Editor<Helper>;
 pub struct Editor(Rl);
 
 impl Editor {
     const HISTFILE: &'static str = ".satis-hist";
 
+    /// Create an editor completing the items and recipes of the loaded config
     pub fn new() -> Disposable<Self> {
-        let mut rl = Rl::new();
+        let config = Config::builder()
+            .completion_type(CompletionType::List)
+            .build();
+        let mut rl = Rl::with_config(config);
+        rl.set_helper(Some(Helper::new()));
 
         rl.load_history(Self::HISTFILE)
             .map_err(|e| error!("Failed to load history: {:?}", e))